@@ -0,0 +1,149 @@
+//! Compares the peak heap usage of the different ways of loading a large
+//! compilation database, and the heap usage of holding one in memory
+//!
+//! Run with `cargo bench --bench memory`
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use compile_commands::{CommandStream, CompactDatabase, CompilationDatabase};
+
+/// Wraps the system allocator to track the current and peak heap usage
+struct CountingAlloc;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(current, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Generates a database with `entries` entries spread over a handful of
+/// directories, each with a realistic number of flags
+fn synthetic_database(entries: usize) -> String {
+    let mut json = String::from("[\n");
+    for i in 0..entries {
+        let dir = format!("/home/user/project/components/module_{}", i % 16);
+        let flags = (0..40)
+            .map(|flag| format!("\"-I/home/user/project/third_party/lib_{flag}/include\""))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if i > 0 {
+            json.push_str(",\n");
+        }
+        json.push_str(&format!(
+            "{{\"directory\": \"/home/user/project/out\", \"file\": \"{dir}/file_{i}.cc\", \
+             \"arguments\": [\"/usr/bin/clang++\", \"-DNDEBUG\", \"-std=c++20\", {flags}, \
+             \"-c\", \"-o\", \"obj/file_{i}.o\", \"{dir}/file_{i}.cc\"]}}"
+        ));
+    }
+    json.push_str("\n]\n");
+    json
+}
+
+/// Runs `f`, returning its result along with the peak heap usage above the
+/// usage before it ran
+fn measure<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let baseline = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    let peak = PEAK.load(Ordering::Relaxed) - baseline;
+    println!(
+        "{name:<40} peak {:>8.1} MiB  {:>8.1?}",
+        peak as f64 / (1024.0 * 1024.0),
+        elapsed
+    );
+    result
+}
+
+/// Runs `f`, returning its result along with the heap usage it retains
+fn retained<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let baseline = CURRENT.load(Ordering::Relaxed);
+    let result = f();
+    let retained = CURRENT.load(Ordering::Relaxed) - baseline;
+    println!(
+        "{name:<40} retained {:>8.1} MiB",
+        retained as f64 / (1024.0 * 1024.0)
+    );
+    result
+}
+
+fn main() {
+    loading();
+    println!();
+    holding();
+}
+
+fn holding() {
+    let json = synthetic_database(50_000);
+    println!(
+        "synthetic database: 50000 entries, {:.1} MiB\n",
+        json.len() as f64 / (1024.0 * 1024.0)
+    );
+
+    let db = retained("CompilationDatabase", || {
+        json.parse::<CompilationDatabase>().unwrap()
+    });
+    let compact = retained("CompactDatabase", || CompactDatabase::from(&db));
+    assert_eq!(compact.len(), db.len());
+    println!(
+        "\n{} distinct strings, {} distinct argument prefixes",
+        compact.interner().len(),
+        compact.prefix_count()
+    );
+}
+
+fn loading() {
+    let json = synthetic_database(20_000);
+    println!(
+        "synthetic database: 20000 entries, {:.1} MiB\n",
+        json.len() as f64 / (1024.0 * 1024.0)
+    );
+    let prefix = Path::new("/home/user/project/components/module_3");
+
+    let db = measure("serde_json::from_reader", || {
+        serde_json::from_reader::<_, CompilationDatabase>(json.as_bytes()).unwrap()
+    });
+    drop(db);
+
+    let db = measure("CompilationDatabase::from_reader", || {
+        CompilationDatabase::from_reader(json.as_bytes()).unwrap()
+    });
+    drop(db);
+
+    let count = measure("CommandStream (count only)", || {
+        CommandStream::new(json.as_bytes())
+            .map(Result::unwrap)
+            .count()
+    });
+    assert_eq!(count, 20_000);
+
+    let db = measure("from_reader_filtered (1/16 kept)", || {
+        CompilationDatabase::from_reader_filtered(json.as_bytes(), |entry| {
+            entry
+                .resolved_file()
+                .is_some_and(|file| file.starts_with(prefix))
+        })
+        .unwrap()
+    });
+    assert_eq!(db.len(), 20_000 / 16);
+}
@@ -0,0 +1,270 @@
+use std::borrow::Cow;
+use std::fmt::{self, Display};
+
+use crate::{tokenize_command_with, CompileArgs, CompileCommand, TokenizeError, TokenizeMode};
+
+/// What [`CompileCommand::args_with`] should do when an entry has both
+/// `arguments` and `command`
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq)]
+pub enum ConflictPolicy {
+    /// Use `arguments` and ignore `command`, as recommended by the spec
+    #[default]
+    PreferArguments,
+    /// Use `command` and ignore `arguments`
+    PreferCommand,
+    /// Use `arguments`, but fail with [`ArgsError::Conflict`] if `command`
+    /// splits into a different argument vector
+    Error,
+}
+
+/// Options controlling how [`CompileCommand::args_with`] normalizes an entry's
+/// argument vector
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct ArgsOptions {
+    /// The compiler prepended to the flags of a `compile_flags.txt` derived
+    /// entry, which do not name one themselves. If `None`, the flags are
+    /// returned as is. Defaults to `clang`.
+    pub compiler: Option<String>,
+    /// The quoting rules used to split `command`. If `None`, they are detected
+    /// with [`CompileCommand::tokenize_mode`].
+    pub tokenize_mode: Option<TokenizeMode>,
+    /// Which field wins when both `arguments` and `command` are present
+    pub conflict: ConflictPolicy,
+}
+
+impl Default for ArgsOptions {
+    fn default() -> Self {
+        Self {
+            compiler: Some(String::from("clang")),
+            tokenize_mode: None,
+            conflict: ConflictPolicy::default(),
+        }
+    }
+}
+
+/// Errors that can occur while resolving an entry's argument vector
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum ArgsError {
+    /// The entry has neither `arguments` nor `command`
+    Missing,
+    /// The `command` field could not be split into arguments
+    Tokenize(TokenizeError),
+    /// `arguments` and `command` are both present and disagree
+    Conflict {
+        arguments: Vec<String>,
+        command: Vec<String>,
+    },
+}
+
+impl Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "entry has neither `arguments` nor `command`"),
+            Self::Tokenize(e) => write!(f, "failed to split `command`: {e}"),
+            Self::Conflict { arguments, command } => write!(
+                f,
+                "`arguments` {arguments:?} disagrees with `command` {command:?}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ArgsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Tokenize(e) => Some(e),
+            Self::Missing | Self::Conflict { .. } => None,
+        }
+    }
+}
+
+impl From<TokenizeError> for ArgsError {
+    fn from(e: TokenizeError) -> Self {
+        Self::Tokenize(e)
+    }
+}
+
+impl CompileCommand {
+    /// Returns the entry's argument vector, with the compiler as the first
+    /// element, using the default [`ArgsOptions`]
+    ///
+    /// # Errors
+    ///
+    /// See [`CompileCommand::args_with`]
+    pub fn args(&self) -> Result<Cow<'_, [String]>, ArgsError> {
+        self.args_with(&ArgsOptions::default())
+    }
+
+    /// Returns the entry's argument vector, resolved from whichever of
+    /// `arguments` and `command` is present
+    ///
+    /// `arguments` are borrowed, while `command` is split with the configured
+    /// quoting rules. The flags of a `compile_flags.txt` derived entry are
+    /// prefixed with `options.compiler`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if neither field is present, `command` is needed and
+    /// cannot be split, or the fields disagree under `ConflictPolicy::Error`
+    pub fn args_with(&self, options: &ArgsOptions) -> Result<Cow<'_, [String]>, ArgsError> {
+        let split_command = |cmd: &str| {
+            let mode = options
+                .tokenize_mode
+                .unwrap_or_else(|| self.tokenize_mode());
+            tokenize_command_with(cmd, mode)
+        };
+
+        let arguments = match (&self.arguments, &self.command, options.conflict) {
+            (None, None, _) => return Err(ArgsError::Missing),
+            (None, Some(cmd), _) | (Some(_), Some(cmd), ConflictPolicy::PreferCommand) => {
+                return Ok(Cow::Owned(split_command(cmd)?));
+            }
+            (Some(arguments), Some(cmd), ConflictPolicy::Error) => {
+                let command = split_command(cmd)?;
+                let (CompileArgs::Arguments(args) | CompileArgs::Flags(args)) = arguments;
+                if *args != command {
+                    return Err(ArgsError::Conflict {
+                        arguments: args.clone(),
+                        command,
+                    });
+                }
+                arguments
+            }
+            (Some(arguments), _, _) => arguments,
+        };
+
+        match (arguments, &options.compiler) {
+            (CompileArgs::Flags(flags), Some(compiler)) => {
+                let mut args = Vec::with_capacity(flags.len() + 1);
+                args.push(compiler.clone());
+                args.extend_from_slice(flags);
+                Ok(Cow::Owned(args))
+            }
+            (CompileArgs::Arguments(args) | CompileArgs::Flags(args), _) => Ok(Cow::Borrowed(args)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{from_compile_flags_txt, SourceFile};
+
+    fn comp_cmd(arguments: Option<&[&str]>, command: Option<&str>) -> CompileCommand {
+        CompileCommand {
+            directory: PathBuf::from("/build"),
+            file: SourceFile::File(PathBuf::from("main.c")),
+            arguments: arguments
+                .map(|args| CompileArgs::Arguments(args.iter().map(ToString::to_string).collect())),
+            command: command.map(ToString::to_string),
+            output: None,
+        }
+    }
+
+    #[test]
+    fn it_borrows_arguments() {
+        let comp_cmd = comp_cmd(Some(&["cc", "-c", "main.c"]), None);
+
+        let args = comp_cmd.args().unwrap();
+        assert!(matches!(args, Cow::Borrowed(_)));
+        assert_eq!(*args, ["cc", "-c", "main.c"]);
+    }
+
+    #[test]
+    fn it_splits_command() {
+        let comp_cmd = comp_cmd(None, Some(r#"cc "-DX=a b" -c main.c"#));
+
+        assert_eq!(*comp_cmd.args().unwrap(), ["cc", "-DX=a b", "-c", "main.c"]);
+    }
+
+    #[test]
+    fn it_detects_command_tokenize_mode() {
+        let comp_cmd = comp_cmd(None, Some(r#"cl.exe "/IC:\inc\\" /c main.c"#));
+
+        assert_eq!(
+            *comp_cmd.args().unwrap(),
+            ["cl.exe", r"/IC:\inc\", "/c", "main.c"]
+        );
+
+        let options = ArgsOptions {
+            tokenize_mode: Some(TokenizeMode::Spec),
+            ..ArgsOptions::default()
+        };
+        assert_eq!(
+            *comp_cmd.args_with(&options).unwrap(),
+            ["cl.exe", r"/IC:inc\", "/c", "main.c"]
+        );
+    }
+
+    #[test]
+    fn it_injects_a_compiler_for_flags() {
+        let comp_data = from_compile_flags_txt(&PathBuf::from("/proj"), "-xc++\n-Iinclude");
+
+        assert_eq!(
+            *comp_data[0].args().unwrap(),
+            ["clang", "-xc++", "-Iinclude"]
+        );
+
+        let options = ArgsOptions {
+            compiler: Some(String::from("/usr/bin/g++")),
+            ..ArgsOptions::default()
+        };
+        assert_eq!(
+            *comp_data[0].args_with(&options).unwrap(),
+            ["/usr/bin/g++", "-xc++", "-Iinclude"]
+        );
+
+        let options = ArgsOptions {
+            compiler: None,
+            ..ArgsOptions::default()
+        };
+        let args = comp_data[0].args_with(&options).unwrap();
+        assert!(matches!(args, Cow::Borrowed(_)));
+        assert_eq!(*args, ["-xc++", "-Iinclude"]);
+    }
+
+    #[test]
+    fn it_applies_conflict_policies() {
+        let conflicting = comp_cmd(Some(&["cc", "-c", "main.c"]), Some("cc -O2 -c main.c"));
+        let with_policy = |conflict| ArgsOptions {
+            conflict,
+            ..ArgsOptions::default()
+        };
+
+        assert_eq!(*conflicting.args().unwrap(), ["cc", "-c", "main.c"]);
+        assert_eq!(
+            *conflicting
+                .args_with(&with_policy(ConflictPolicy::PreferCommand))
+                .unwrap(),
+            ["cc", "-O2", "-c", "main.c"]
+        );
+        assert_eq!(
+            conflicting.args_with(&with_policy(ConflictPolicy::Error)),
+            Err(ArgsError::Conflict {
+                arguments: vec!["cc".into(), "-c".into(), "main.c".into()],
+                command: vec!["cc".into(), "-O2".into(), "-c".into(), "main.c".into()],
+            })
+        );
+
+        let agreeing = comp_cmd(Some(&["cc", "-c", "main.c"]), Some("cc -c main.c"));
+        assert_eq!(
+            *agreeing
+                .args_with(&with_policy(ConflictPolicy::Error))
+                .unwrap(),
+            ["cc", "-c", "main.c"]
+        );
+    }
+
+    #[test]
+    fn it_reports_args_errors() {
+        assert_eq!(comp_cmd(None, None).args(), Err(ArgsError::Missing));
+        assert_eq!(
+            comp_cmd(None, Some("cc \"main.c")).args(),
+            Err(ArgsError::Tokenize(TokenizeError::UnterminatedQuote {
+                position: 3
+            }))
+        );
+    }
+}
@@ -0,0 +1,405 @@
+use std::path::{Path, PathBuf};
+
+use crate::language::file_input;
+use crate::target::arch_variant;
+use crate::{
+    AddressModel, Arch, ArgsError, CompileCommand, Driver, DriverInfo, Flag, Language,
+    ParsedCommand, SourceFile, Stage,
+};
+
+/// The assembler syntax a translation unit is written in
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum AsmDialect {
+    /// The GNU assembler's syntax, also accepted by clang's integrated
+    /// assembler
+    Gas,
+    Nasm,
+    Yasm,
+}
+
+/// What an assembly language server needs to know about an assembly
+/// translation unit
+#[derive(Debug, Clone, Default, Hash, Eq, PartialEq)]
+pub struct AsmInfo {
+    pub dialect: Option<AsmDialect>,
+    /// Whether the source is run through the C preprocessor first, as for
+    /// `.S` files and `-x assembler-with-cpp`
+    pub preprocessed: bool,
+    /// The target architecture, e.g. `x86_64` or `armv8-a`
+    pub arch: Option<String>,
+    /// nasm's and yasm's output format, e.g. `elf64`
+    pub format: Option<String>,
+    /// The directories searched by `%include`, `.include` and `#include`
+    pub include_dirs: Vec<PathBuf>,
+    /// Macros defined with `-D`, as `(name, value)` pairs
+    pub defines: Vec<(String, Option<String>)>,
+    /// Symbols defined with the GNU assembler's `--defsym`, as `(name, value)`
+    /// pairs
+    pub symbols: Vec<(String, Option<String>)>,
+}
+
+/// The architecture a nasm or yasm output format targets, or `None` for
+/// formats such as `bin` that don't imply one
+fn format_arch(format: &str) -> Option<&'static str> {
+    match format {
+        "elf64" | "elfx32" | "macho64" | "win64" | "x64" => Some("x86_64"),
+        "elf" | "elf32" | "macho" | "macho32" | "win32" | "coff" | "aout" | "aoutb" | "as86"
+        | "obj" | "xdf" => Some("i386"),
+        _ => None,
+    }
+}
+
+/// Spells x86 architectures the way target triples do, e.g. yasm's `amd64` as
+/// `x86_64`
+fn normalize_arch(arch: &str) -> &str {
+    match Arch::from_triple_arch(arch) {
+        Arch::X86_64 => "x86_64",
+        Arch::X86 if arch == "x86" => "i386",
+        _ => arch,
+    }
+}
+
+impl AsmInfo {
+    /// Collects the assembler settings of a parsed command compiling `file`,
+    /// or its first input if `file` is `None`. Returns `None` if the command
+    /// doesn't assemble an assembly source.
+    ///
+    /// The architecture is taken from, in order of preference, the last
+    /// `-march=`, the address model selected by `-m32`, `-m64`, `--32` or
+    /// `--64` applied to the target triple's architecture, or to x86 if the
+    /// triple is x86 or unknown, the architecture implied by nasm's or yasm's output format, and
+    /// the architecture of the target triple. Aliases of x86-64 such as yasm's
+    /// `amd64` are reported as `x86_64`.
+    #[must_use]
+    pub fn from_parsed(parsed: &ParsedCommand, file: Option<&Path>) -> Option<Self> {
+        let driver = DriverInfo::from_program(parsed.program.as_deref().unwrap_or_default());
+        let file = file.or_else(|| parsed.inputs().next().map(Path::new));
+        let language = if driver.driver.is_assembler() {
+            Language::Assembler
+        } else {
+            parsed.input_language(file.and_then(Path::to_str))?
+        };
+
+        let dialect = match driver.driver {
+            Driver::Nasm => Some(AsmDialect::Nasm),
+            Driver::Yasm => Some(AsmDialect::Yasm),
+            Driver::GnuAs | Driver::Gcc | Driver::Gxx | Driver::Clang | Driver::ClangXX => {
+                Some(AsmDialect::Gas)
+            }
+            _ => None,
+        };
+        let preprocessed = match language {
+            Language::Assembler => false,
+            Language::AssemblerWithCpp => true,
+            _ => return None,
+        };
+
+        // The flags read by the assembler itself: those of a standalone
+        // assembler, or those forwarded with `-Wa,` and `-Xassembler`
+        let asm_flags: Vec<&Flag> = parsed.stage_flags(Stage::Assembler).collect();
+        let own_flags: Vec<&Flag> = if driver.driver.is_assembler() {
+            parsed.args.iter().map(|arg| &arg.flag).collect()
+        } else {
+            asm_flags.clone()
+        };
+        let own_flags = || own_flags.iter().copied();
+
+        let format = own_flags()
+            .filter_map(|flag| match flag {
+                Flag::OutputFormat(format) => Some(format.as_str()),
+                _ => None,
+            })
+            .next_back();
+        let address_model = own_flags()
+            .filter_map(|flag| match flag {
+                Flag::AddressModel(model) => Some(*model),
+                _ => None,
+            })
+            .next_back()
+            .or_else(|| parsed.address_model());
+        let triple = parsed.target().or(driver.target.as_deref());
+        let triple_arch = triple.and_then(|triple| triple.split('-').next());
+        let address_model_arch = address_model.map(|model| match triple_arch {
+            Some(arch)
+                if !matches!(
+                    Arch::from_triple_arch(arch),
+                    Arch::X86 | Arch::X86_64 | Arch::Other
+                ) =>
+            {
+                arch_variant(arch, model).unwrap_or(arch)
+            }
+            _ => match model {
+                AddressModel::Bits32 => "i386",
+                AddressModel::Bits64 => "x86_64",
+            },
+        });
+        let arch = own_flags()
+            .filter_map(|flag| match flag {
+                Flag::Arch(arch) => Some(arch.as_str()),
+                _ => None,
+            })
+            .next_back()
+            .or_else(|| parsed.arch())
+            .or(address_model_arch)
+            .or_else(|| format.and_then(format_arch))
+            .or(triple_arch)
+            .map(normalize_arch);
+
+        let mut include_dirs: Vec<PathBuf> = parsed
+            .include_dirs()
+            .map(|(_, path)| path.to_path_buf())
+            .collect();
+        include_dirs.extend(asm_flags.iter().filter_map(|flag| match flag {
+            Flag::Include { path, .. } => Some(path.clone()),
+            _ => None,
+        }));
+
+        let defines = if preprocessed || matches!(driver.driver, Driver::Nasm | Driver::Yasm) {
+            parsed
+                .defines()
+                .map(|(name, value)| (name.to_string(), value.map(ToString::to_string)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let symbols = own_flags()
+            .filter_map(|flag| match flag {
+                Flag::Symbol { name, value } => Some((name.clone(), value.clone())),
+                _ => None,
+            })
+            .collect();
+
+        Some(Self {
+            dialect,
+            preprocessed,
+            arch: arch.map(ToString::to_string),
+            format: format.map(ToString::to_string),
+            include_dirs,
+            defines,
+            symbols,
+        })
+    }
+}
+
+impl CompileCommand {
+    /// Collects the entry's assembler settings, see [`AsmInfo::from_parsed`].
+    /// Returns `None` if the entry doesn't assemble an assembly source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument vector cannot be determined, see
+    /// [`CompileCommand::args`]
+    pub fn asm_info(&self) -> Result<Option<AsmInfo>, ArgsError> {
+        let parsed = self.parse()?;
+        let file = match (file_input(self, &parsed), &self.file) {
+            (Some(input), _) => Some(Path::new(input)),
+            (None, SourceFile::File(file)) => Some(file.as_path()),
+            (None, SourceFile::All) => None,
+        };
+        Ok(AsmInfo::from_parsed(&parsed, file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompilationDatabase;
+
+    const FIXTURES: &str = r#"[
+        {
+            "directory": "/proj/build",
+            "file": "../src/start.asm",
+            "arguments": ["nasm", "-f", "elf64", "-I", "../include/", "-dDEBUG", "-DLEVEL=2", "-o", "start.o", "../src/start.asm"]
+        },
+        {
+            "directory": "/proj/build",
+            "file": "../src/boot.asm",
+            "command": "/usr/bin/nasm -fwin32 -i../include/ -Pmacros.inc ../src/boot.asm -o boot.obj"
+        },
+        {
+            "directory": "/proj/build",
+            "file": "../src/simd.asm",
+            "command": "yasm -f macho64 -m amd64 -I ../include -D HAVE_AVX2 -o simd.o ../src/simd.asm"
+        },
+        {
+            "directory": "/proj/build",
+            "file": "../src/crt0.s",
+            "command": "as --32 -I ../include --defsym DEBUG=1 --defsym=BARE -o crt0.o ../src/crt0.s"
+        },
+        {
+            "directory": "/proj/build",
+            "file": "../src/vectors.s",
+            "command": "aarch64-linux-gnu-as -march=armv8.2-a -o vectors.o ../src/vectors.s"
+        },
+        {
+            "directory": "/proj/build",
+            "file": "../src/entry.S",
+            "command": "gcc -DASM -I../include -Wa,--defsym,STACK=0x1000,-I../asm -m32 -c ../src/entry.S -o entry.o"
+        },
+        {
+            "directory": "/proj/build",
+            "file": "../src/switch.s",
+            "command": "clang --target=riscv64-unknown-elf -DIGNORED -c ../src/switch.s -o switch.o"
+        },
+        {
+            "directory": "/proj/build",
+            "file": "../src/ops.inc",
+            "command": "clang -x assembler-with-cpp -DOPS -c ../src/ops.inc -o ops.o"
+        },
+        {
+            "directory": "/proj/build",
+            "file": "../src/vsx.S",
+            "command": "powerpc64le-linux-gnu-gcc -m64 -c ../src/vsx.S -o vsx.o"
+        },
+        {
+            "directory": "/proj/build",
+            "file": "../src/mbr.asm",
+            "command": "nasm -f bin -o mbr.bin ../src/mbr.asm"
+        },
+        {
+            "directory": "/proj/build",
+            "file": "../src/main.c",
+            "command": "gcc -Wa,--64 -c ../src/main.c -o main.o"
+        }
+    ]"#;
+
+    fn pairs(pairs: &[(&str, Option<&str>)]) -> Vec<(String, Option<String>)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.map(ToString::to_string)))
+            .collect()
+    }
+
+    #[test]
+    fn it_extracts_assembler_settings() {
+        let db: CompilationDatabase = FIXTURES.parse().unwrap();
+        let expected = [
+            Some(AsmInfo {
+                dialect: Some(AsmDialect::Nasm),
+                preprocessed: false,
+                arch: Some("x86_64".into()),
+                format: Some("elf64".into()),
+                include_dirs: vec!["../include/".into()],
+                defines: pairs(&[("DEBUG", None), ("LEVEL", Some("2"))]),
+                symbols: Vec::new(),
+            }),
+            Some(AsmInfo {
+                dialect: Some(AsmDialect::Nasm),
+                preprocessed: false,
+                arch: Some("i386".into()),
+                format: Some("win32".into()),
+                include_dirs: vec!["../include/".into()],
+                defines: Vec::new(),
+                symbols: Vec::new(),
+            }),
+            Some(AsmInfo {
+                dialect: Some(AsmDialect::Yasm),
+                preprocessed: false,
+                arch: Some("x86_64".into()),
+                format: Some("macho64".into()),
+                include_dirs: vec!["../include".into()],
+                defines: pairs(&[("HAVE_AVX2", None)]),
+                symbols: Vec::new(),
+            }),
+            Some(AsmInfo {
+                dialect: Some(AsmDialect::Gas),
+                preprocessed: false,
+                arch: Some("i386".into()),
+                format: None,
+                include_dirs: vec!["../include".into()],
+                defines: Vec::new(),
+                symbols: pairs(&[("DEBUG", Some("1")), ("BARE", None)]),
+            }),
+            Some(AsmInfo {
+                dialect: Some(AsmDialect::Gas),
+                preprocessed: false,
+                arch: Some("armv8.2-a".into()),
+                format: None,
+                include_dirs: Vec::new(),
+                defines: Vec::new(),
+                symbols: Vec::new(),
+            }),
+            Some(AsmInfo {
+                dialect: Some(AsmDialect::Gas),
+                preprocessed: true,
+                arch: Some("i386".into()),
+                format: None,
+                include_dirs: vec!["../include".into(), "../asm".into()],
+                defines: pairs(&[("ASM", None)]),
+                symbols: pairs(&[("STACK", Some("0x1000"))]),
+            }),
+            Some(AsmInfo {
+                dialect: Some(AsmDialect::Gas),
+                preprocessed: false,
+                arch: Some("riscv64".into()),
+                format: None,
+                include_dirs: Vec::new(),
+                defines: Vec::new(),
+                symbols: Vec::new(),
+            }),
+            Some(AsmInfo {
+                dialect: Some(AsmDialect::Gas),
+                preprocessed: true,
+                arch: None,
+                format: None,
+                include_dirs: Vec::new(),
+                defines: pairs(&[("OPS", None)]),
+                symbols: Vec::new(),
+            }),
+            Some(AsmInfo {
+                dialect: Some(AsmDialect::Gas),
+                preprocessed: true,
+                arch: Some("powerpc64le".into()),
+                format: None,
+                include_dirs: Vec::new(),
+                defines: Vec::new(),
+                symbols: Vec::new(),
+            }),
+            Some(AsmInfo {
+                dialect: Some(AsmDialect::Nasm),
+                preprocessed: false,
+                arch: None,
+                format: Some("bin".into()),
+                include_dirs: Vec::new(),
+                defines: Vec::new(),
+                symbols: Vec::new(),
+            }),
+            None,
+        ];
+
+        assert_eq!(db.len(), expected.len());
+        for (entry, expected) in db.iter().zip(expected) {
+            assert_eq!(entry.asm_info().unwrap(), expected, "reading {entry}");
+        }
+    }
+
+    #[test]
+    fn it_maps_formats_to_arches() {
+        let cases: &[(&str, Option<&str>)] = &[
+            ("elf64", Some("x86_64")),
+            ("elfx32", Some("x86_64")),
+            ("win64", Some("x86_64")),
+            ("x64", Some("x86_64")),
+            ("elf", Some("i386")),
+            ("win32", Some("i386")),
+            ("bin", None),
+            ("ith", None),
+            ("srec", None),
+            ("dbg", None),
+            ("rdf", None),
+        ];
+
+        for (format, arch) in cases {
+            assert_eq!(format_arch(format), *arch, "mapping {format}");
+        }
+    }
+
+    #[test]
+    fn it_round_trips_assembler_commands() {
+        let db: CompilationDatabase = FIXTURES.parse().unwrap();
+        for entry in db.iter() {
+            let args = entry.args().unwrap();
+            assert_eq!(ParsedCommand::parse(&args).to_args(), *args);
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use serde::de::{Deserializer, Error as SerdeError, Visitor};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+use crate::path::{normalize, resolve};
+use crate::{CompilationDatabase, CompileArgs, CompileCommand, Error, SourceFile};
+
+/// A string borrowed from the input whenever it contains no escapes
+struct BorrowedStr<'a>(Cow<'a, str>);
+
+impl<'de> Deserialize<'de> for BorrowedStr<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BorrowedStrVisitor;
+
+        impl<'de> Visitor<'de> for BorrowedStrVisitor {
+            type Value = BorrowedStr<'de>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a string")
+            }
+
+            fn visit_borrowed_str<E>(self, value: &'de str) -> Result<Self::Value, E>
+            where
+                E: SerdeError,
+            {
+                Ok(BorrowedStr(Cow::Borrowed(value)))
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: SerdeError,
+            {
+                Ok(BorrowedStr(Cow::Owned(value.to_string())))
+            }
+        }
+
+        deserializer.deserialize_str(BorrowedStrVisitor)
+    }
+}
+
+fn cow_str<'de, D>(deserializer: D) -> Result<Cow<'de, str>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    BorrowedStr::deserialize(deserializer).map(|s| s.0)
+}
+
+fn option_cow_str<'de, D>(deserializer: D) -> Result<Option<Cow<'de, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<BorrowedStr>::deserialize(deserializer).map(|s| s.map(|s| s.0))
+}
+
+fn option_cow_strs<'de, D>(deserializer: D) -> Result<Option<Vec<Cow<'de, str>>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<Vec<BorrowedStr>>::deserialize(deserializer)
+        .map(|args| args.map(|args| args.into_iter().map(|s| s.0).collect()))
+}
+
+/// A `CompileCommand` that borrows its strings from the JSON text it was
+/// deserialized from
+///
+/// Strings are only copied when they contain JSON escapes, making this cheaper
+/// than `CompileCommand` for tools that load a database, look up a few files
+/// and exit. Convert to the owned `CompileCommand` with
+/// [`CompileCommandRef::into_owned`].
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Deserialize)]
+pub struct CompileCommandRef<'a> {
+    /// See [`CompileCommand::directory`]
+    #[serde(borrow, deserialize_with = "cow_str")]
+    pub directory: Cow<'a, str>,
+    /// See [`CompileCommand::file`]. `None` corresponds to `SourceFile::All`.
+    #[serde(borrow, deserialize_with = "option_cow_str")]
+    pub file: Option<Cow<'a, str>>,
+    /// See [`CompileCommand::arguments`]
+    #[serde(borrow, default, deserialize_with = "option_cow_strs")]
+    pub arguments: Option<Vec<Cow<'a, str>>>,
+    /// See [`CompileCommand::command`]
+    #[serde(borrow, default, deserialize_with = "option_cow_str")]
+    pub command: Option<Cow<'a, str>>,
+    /// See [`CompileCommand::output`]
+    #[serde(borrow, default, deserialize_with = "option_cow_str")]
+    pub output: Option<Cow<'a, str>>,
+}
+
+impl CompileCommandRef<'_> {
+    /// Returns `file` joined onto `directory` and lexically normalized, or `None`
+    /// for a `compile_flags.txt` derived entry
+    #[must_use]
+    pub fn resolved_file(&self) -> Option<PathBuf> {
+        let file = self.file.as_deref()?;
+        Some(resolve(Path::new(&*self.directory), Path::new(file)))
+    }
+
+    /// Copies every borrowed string, producing the equivalent `CompileCommand`
+    #[must_use]
+    pub fn into_owned(self) -> CompileCommand {
+        let arguments = self.arguments.map(|args| {
+            let args = args.into_iter().map(Cow::into_owned).collect();
+            if self.file.is_some() {
+                CompileArgs::Arguments(args)
+            } else {
+                CompileArgs::Flags(args)
+            }
+        });
+
+        CompileCommand {
+            directory: PathBuf::from(self.directory.into_owned()),
+            file: self.file.map_or(SourceFile::All, |file| {
+                SourceFile::File(PathBuf::from(file.into_owned()))
+            }),
+            arguments,
+            command: self.command.map(Cow::into_owned),
+            output: self.output.map(|output| PathBuf::from(output.into_owned())),
+        }
+    }
+}
+
+impl From<CompileCommandRef<'_>> for CompileCommand {
+    fn from(entry: CompileCommandRef<'_>) -> Self {
+        entry.into_owned()
+    }
+}
+
+/// A compilation database whose entries borrow from the JSON text it was
+/// parsed from
+///
+/// See [`CompileCommandRef`]
+#[derive(Debug, Clone, Default, Hash, Eq, PartialEq)]
+pub struct CompilationDatabaseRef<'a> {
+    entries: Vec<CompileCommandRef<'a>>,
+}
+
+impl<'a> CompilationDatabaseRef<'a> {
+    /// Parses a compilation database, borrowing from `s`
+    ///
+    /// Entries are checked the same way as by [`CompilationDatabase::from_reader`],
+    /// except that schema errors do not name the offending field
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `s` is not valid JSON, or it does not follow the
+    /// compilation database format
+    pub fn parse(s: &'a str) -> Result<Self, Error> {
+        let raw_entries = serde_json::from_str::<Vec<&'a RawValue>>(s).map_err(|e| {
+            if e.is_data() {
+                Error::Schema {
+                    index: None,
+                    field: None,
+                    message: String::from("expected an array of entries"),
+                }
+            } else {
+                Error::from(e)
+            }
+        })?;
+
+        let entries = raw_entries
+            .into_iter()
+            .enumerate()
+            .map(|(index, raw)| {
+                let entry =
+                    serde_json::from_str::<CompileCommandRef<'a>>(raw.get()).map_err(|e| {
+                        Error::Schema {
+                            index: Some(index),
+                            field: None,
+                            message: e.to_string(),
+                        }
+                    })?;
+                if entry.arguments.is_none() && entry.command.is_none() {
+                    return Err(Error::MissingCommand { index });
+                }
+                Ok(entry)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { entries })
+    }
+
+    /// Returns the first entry for `path`
+    ///
+    /// Unlike [`CompilationDatabase::get`], this is a linear scan. If no entry
+    /// names `path` as its `file`, the first `compile_flags.txt` derived entry
+    /// is returned.
+    #[must_use]
+    pub fn get(&self, path: &Path) -> Option<&CompileCommandRef<'a>> {
+        let path = normalize(path);
+        self.entries
+            .iter()
+            .find(|entry| entry.resolved_file().as_ref() == Some(&path))
+            .or_else(|| self.entries.iter().find(|entry| entry.file.is_none()))
+    }
+
+    /// Copies every borrowed string, producing the equivalent
+    /// `CompilationDatabase`
+    #[must_use]
+    pub fn into_owned(self) -> CompilationDatabase {
+        self.entries
+            .into_iter()
+            .map(CompileCommandRef::into_owned)
+            .collect()
+    }
+}
+
+impl<'a> Deref for CompilationDatabaseRef<'a> {
+    type Target = [CompileCommandRef<'a>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+impl<'a> IntoIterator for CompilationDatabaseRef<'a> {
+    type Item = CompileCommandRef<'a>;
+    type IntoIter = std::vec::IntoIter<CompileCommandRef<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl From<CompilationDatabaseRef<'_>> for CompilationDatabase {
+    fn from(db: CompilationDatabaseRef<'_>) -> Self {
+        db.into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DB: &str = r#"[
+  {"directory": "/proj/build", "file": "../a.c", "arguments": ["cc", "-DX=\"y\"", "../a.c"]},
+  {"directory": "/proj", "file": "b.c", "command": "cc -c b.c", "output": "b.o"},
+  {"directory": "/proj", "file": null, "arguments": ["-Iinc"]}
+]"#;
+
+    #[test]
+    fn it_borrows_unescaped_strings() {
+        let db = CompilationDatabaseRef::parse(DB).unwrap();
+        let args = db[0].arguments.as_ref().unwrap();
+
+        assert!(matches!(db[0].directory, Cow::Borrowed("/proj/build")));
+        assert!(matches!(args[0], Cow::Borrowed("cc")));
+        assert!(matches!(&args[1], Cow::Owned(arg) if arg == r#"-DX="y""#));
+        assert!(matches!(db[1].command, Some(Cow::Borrowed("cc -c b.c"))));
+        assert_eq!(db[2].file, None);
+    }
+
+    #[test]
+    fn it_converts_to_owned() {
+        let db = CompilationDatabaseRef::parse(DB).unwrap();
+
+        assert_eq!(db.into_owned(), DB.parse::<CompilationDatabase>().unwrap());
+    }
+
+    #[test]
+    fn it_looks_up_entries() {
+        let db = CompilationDatabaseRef::parse(DB).unwrap();
+
+        assert_eq!(db.get(Path::new("/proj/a.c")), Some(&db[0]));
+        assert_eq!(db.get(Path::new("/proj/./b.c")), Some(&db[1]));
+        assert_eq!(db.get(Path::new("/elsewhere/c.c")), Some(&db[2]));
+    }
+
+    #[test]
+    fn it_reports_errors() {
+        assert!(matches!(
+            CompilationDatabaseRef::parse("[{"),
+            Err(Error::Syntax { .. })
+        ));
+        assert!(matches!(
+            CompilationDatabaseRef::parse("{}"),
+            Err(Error::Schema { index: None, .. })
+        ));
+        assert!(matches!(
+            CompilationDatabaseRef::parse(r#"[{"directory": "/p", "file": "a.c", "command": 1}]"#),
+            Err(Error::Schema { index: Some(0), .. })
+        ));
+        assert!(matches!(
+            CompilationDatabaseRef::parse(r#"[{"directory": "/p", "file": "a.c"}]"#),
+            Err(Error::MissingCommand { index: 0 })
+        ));
+    }
+}
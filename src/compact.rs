@@ -0,0 +1,441 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::hash::Hash;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::parsed::ValueKind;
+use crate::{CompilationDatabase, CompileArgs, CompileCommand, ParsedCommand, SourceFile};
+
+/// A handle to a string stored in an [`Interner`]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Symbol(u32);
+
+/// Stores each distinct string once, handing out [`Symbol`]s to refer to them
+///
+/// Strings are `str` by default; [`CompactDatabase`] interns paths as `OsStr`.
+#[derive(Debug)]
+pub struct Interner<T: ?Sized = str> {
+    symbols: HashMap<Arc<T>, Symbol>,
+    strings: Vec<Arc<T>>,
+}
+
+impl<T: ?Sized> Default for Interner<T> {
+    fn default() -> Self {
+        Self {
+            symbols: HashMap::new(),
+            strings: Vec::new(),
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for Interner<T> {
+    fn clone(&self) -> Self {
+        Self {
+            symbols: self.symbols.clone(),
+            strings: self.strings.clone(),
+        }
+    }
+}
+
+impl<T: ?Sized + Hash + Eq> Interner<T>
+where
+    for<'a> Arc<T>: From<&'a T>,
+{
+    /// Creates an empty interner
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the symbol for `s`, storing it if it hasn't been seen before
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than `u32::MAX` distinct strings are interned
+    pub fn intern(&mut self, s: &T) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(s) {
+            return symbol;
+        }
+
+        let symbol = Symbol(u32::try_from(self.strings.len()).expect("too many interned strings"));
+        let s: Arc<T> = Arc::from(s);
+        self.strings.push(Arc::clone(&s));
+        self.symbols.insert(s, symbol);
+        symbol
+    }
+
+    /// Returns the string `symbol` refers to
+    ///
+    /// # Panics
+    ///
+    /// Panics if `symbol` was handed out by a different interner
+    #[must_use]
+    pub fn resolve(&self, symbol: Symbol) -> &T {
+        &self.strings[symbol.0 as usize]
+    }
+
+    /// Returns the number of distinct strings stored
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns `true` if no strings are stored
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+/// The interned arguments of a [`CompactCommand`]
+///
+/// The leading arguments shared by many entries, i.e. everything before the
+/// first argument naming the entry's source file or output, are stored once
+/// and shared between entries
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+struct CompactArgs {
+    prefix: Arc<[Symbol]>,
+    suffix: Box<[Symbol]>,
+    /// Whether the arguments came from `CompileArgs::Flags`
+    flags: bool,
+}
+
+/// A `CompileCommand` whose strings are stored in a [`CompactDatabase`]'s
+/// interners. `directory`, `file` and `output` refer to the path interner.
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+struct CompactCommand {
+    directory: Symbol,
+    /// `None` for `SourceFile::All`
+    file: Option<Symbol>,
+    arguments: Option<CompactArgs>,
+    command: Option<Symbol>,
+    output: Option<Symbol>,
+}
+
+/// A memory-compact compilation database
+///
+/// Every string is interned, and identical leading argument sequences are
+/// stored once. For large databases, where most entries share the same
+/// `directory`, compiler and flags, this takes a fraction of the memory of a
+/// [`CompilationDatabase`]. Entries are converted back to plain
+/// `CompileCommand`s on access.
+///
+/// Paths are interned separately from arguments, as `OsStr`s, so paths that
+/// are not valid UTF-8 are kept as is.
+#[derive(Debug, Clone, Default)]
+pub struct CompactDatabase {
+    interner: Interner,
+    paths: Interner<OsStr>,
+    prefixes: HashSet<Arc<[Symbol]>>,
+    entries: Vec<CompactCommand>,
+}
+
+impl CompactDatabase {
+    /// Creates an empty database
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a copy of `entry`
+    pub fn push(&mut self, entry: &CompileCommand) {
+        let file = match &entry.file {
+            SourceFile::All => None,
+            SourceFile::File(file) => Some(file.as_os_str()),
+        };
+        let arguments = entry.arguments.as_ref().map(|arguments| {
+            let (CompileArgs::Arguments(args) | CompileArgs::Flags(args)) = arguments;
+            let flags = matches!(arguments, CompileArgs::Flags(_));
+            let split = shared_prefix_len(args, file, flags);
+            let prefix = self.intern_prefix(&args[..split]);
+            let suffix = args[split..]
+                .iter()
+                .map(|arg| self.interner.intern(arg))
+                .collect();
+            CompactArgs {
+                prefix,
+                suffix,
+                flags,
+            }
+        });
+
+        let compact = CompactCommand {
+            directory: self.paths.intern(entry.directory.as_os_str()),
+            file: file.map(|file| self.paths.intern(file)),
+            arguments,
+            command: entry
+                .command
+                .as_deref()
+                .map(|command| self.interner.intern(command)),
+            output: entry
+                .output
+                .as_ref()
+                .map(|output| self.paths.intern(output.as_os_str())),
+        };
+        self.entries.push(compact);
+    }
+
+    fn intern_prefix(&mut self, args: &[String]) -> Arc<[Symbol]> {
+        let symbols: Vec<Symbol> = args.iter().map(|arg| self.interner.intern(arg)).collect();
+        if let Some(prefix) = self.prefixes.get(symbols.as_slice()) {
+            return Arc::clone(prefix);
+        }
+
+        let prefix: Arc<[Symbol]> = Arc::from(symbols);
+        self.prefixes.insert(Arc::clone(&prefix));
+        prefix
+    }
+
+    /// Returns the entry at `index`, converted back to a `CompileCommand`
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<CompileCommand> {
+        self.entries.get(index).map(|entry| self.expand(entry))
+    }
+
+    fn expand(&self, entry: &CompactCommand) -> CompileCommand {
+        let string = |symbol| self.interner.resolve(symbol).to_string();
+        let path = |symbol| PathBuf::from(self.paths.resolve(symbol));
+
+        CompileCommand {
+            directory: path(entry.directory),
+            file: entry
+                .file
+                .map_or(SourceFile::All, |file| SourceFile::File(path(file))),
+            arguments: entry.arguments.as_ref().map(|arguments| {
+                let args = arguments
+                    .prefix
+                    .iter()
+                    .chain(arguments.suffix.iter())
+                    .map(|&arg| string(arg))
+                    .collect();
+                if arguments.flags {
+                    CompileArgs::Flags(args)
+                } else {
+                    CompileArgs::Arguments(args)
+                }
+            }),
+            command: entry.command.map(string),
+            output: entry.output.map(path),
+        }
+    }
+
+    /// Returns every entry, converted back to `CompileCommand`s
+    pub fn iter(&self) -> impl Iterator<Item = CompileCommand> + '_ {
+        self.entries.iter().map(|entry| self.expand(entry))
+    }
+
+    /// Returns the number of entries
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no entries
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the interner holding the arguments and commands in the database
+    #[must_use]
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
+
+    /// Returns the interner holding the `directory`, `file` and `output` paths
+    /// in the database
+    #[must_use]
+    pub fn path_interner(&self) -> &Interner<OsStr> {
+        &self.paths
+    }
+
+    /// Returns the number of distinct leading argument sequences stored
+    #[must_use]
+    pub fn prefix_count(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    /// Converts every entry back into a plain [`CompilationDatabase`]
+    #[must_use]
+    pub fn to_database(&self) -> CompilationDatabase {
+        self.iter().collect()
+    }
+}
+
+/// Returns the number of leading arguments likely to be shared with other
+/// entries, i.e. those before the first one naming the source file or output.
+/// `flags` is `true` for `CompileArgs::Flags`, which has no compiler to skip.
+fn shared_prefix_len(args: &[String], file: Option<&OsStr>, flags: bool) -> usize {
+    let program: &[&str] = if flags { &[""] } else { &[] };
+    let argv: Vec<&str> = program
+        .iter()
+        .copied()
+        .chain(args.iter().map(String::as_str))
+        .collect();
+    let parsed = ParsedCommand::parse(&argv);
+
+    let mut len = usize::from(parsed.program.is_some()) - program.len();
+    for arg in &parsed.args {
+        let names_file = arg.raw.iter().any(|raw| Some(OsStr::new(raw)) == file);
+        let is_output = arg.value(parsed.dialect).is_some_and(|value| {
+            matches!(value.kind, ValueKind::Output | ValueKind::DependencyTarget)
+        });
+        if names_file || is_output {
+            return len;
+        }
+        len += arg.raw.len();
+    }
+    args.len()
+}
+
+impl From<&CompilationDatabase> for CompactDatabase {
+    fn from(db: &CompilationDatabase) -> Self {
+        db.iter().collect()
+    }
+}
+
+impl From<&CompactDatabase> for CompilationDatabase {
+    fn from(db: &CompactDatabase) -> Self {
+        db.to_database()
+    }
+}
+
+impl<'a> FromIterator<&'a CompileCommand> for CompactDatabase {
+    fn from_iter<I: IntoIterator<Item = &'a CompileCommand>>(iter: I) -> Self {
+        let mut db = Self::new();
+        for entry in iter {
+            db.push(entry);
+        }
+        db
+    }
+}
+
+impl FromIterator<CompileCommand> for CompactDatabase {
+    fn from_iter<I: IntoIterator<Item = CompileCommand>>(iter: I) -> Self {
+        let mut db = Self::new();
+        for entry in iter {
+            db.push(&entry);
+        }
+        db
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn entry(file: &str) -> CompileCommand {
+        CompileCommand {
+            directory: PathBuf::from("/proj/build"),
+            file: SourceFile::File(PathBuf::from(file)),
+            arguments: Some(CompileArgs::Arguments(
+                ["clang++", "-Iinclude", "-DNDEBUG", "-c", "-o"]
+                    .iter()
+                    .map(ToString::to_string)
+                    .chain([format!("{file}.o"), file.to_string()])
+                    .collect(),
+            )),
+            command: None,
+            output: Some(PathBuf::from(format!("{file}.o"))),
+        }
+    }
+
+    #[test]
+    fn it_interns_strings() {
+        let mut interner = Interner::new();
+        let a = interner.intern("a");
+        let b = interner.intern("b");
+
+        assert_eq!(interner.intern("a"), a);
+        assert_ne!(a, b);
+        assert_eq!(interner.resolve(b), "b");
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn it_round_trips_entries() {
+        let mut db: CompilationDatabase = ["a.cc", "b.cc", "c.cc"].into_iter().map(entry).collect();
+        db.push(CompileCommand {
+            directory: PathBuf::from("/proj"),
+            file: SourceFile::File(PathBuf::from("d.c")),
+            arguments: None,
+            command: Some(String::from("cc -c d.c")),
+            output: None,
+        });
+        db.extend(crate::from_compile_flags_txt(
+            Path::new("/proj"),
+            "-xc++\n-Iinc",
+        ));
+
+        let compact = CompactDatabase::from(&db);
+        assert_eq!(compact.len(), db.len());
+        assert_eq!(compact.get(1).as_ref(), Some(&db[1]));
+        assert_eq!(compact.get(db.len()), None);
+        assert_eq!(compact.to_database(), db);
+    }
+
+    #[test]
+    fn it_shares_argument_prefixes() {
+        let compact: CompactDatabase = (0..100).map(|i| entry(&format!("f{i}.cc"))).collect();
+
+        // One prefix for the compiler and flags shared by every entry
+        assert_eq!(compact.prefix_count(), 1);
+        // The shared arguments plus a file and output argument per entry
+        assert_eq!(compact.interner().len(), 5 + 2 * 100);
+        // The directory plus a file and output path per entry
+        assert_eq!(compact.path_interner().len(), 1 + 2 * 100);
+        let prefix = &compact.entries[0].arguments.as_ref().unwrap().prefix;
+        assert!(Arc::ptr_eq(
+            prefix,
+            &compact.entries[99].arguments.as_ref().unwrap().prefix
+        ));
+    }
+
+    #[test]
+    fn it_splits_at_the_source_file_or_output() {
+        let cases: &[(&[&str], Option<&str>, bool, usize)] = &[
+            (&["cc", "-O2", "a.c"], Some("a.c"), false, 2),
+            (&["cc", "-oa.o", "a.c"], Some("a.c"), false, 1),
+            (&["cc", "-MD", "-MF", "a.d", "a.c"], Some("a.c"), false, 2),
+            (&["cc", "-MT", "a.o", "-c", "a.c"], Some("a.c"), false, 1),
+            (&["cl", "/Foa.obj", "a.c"], Some("a.c"), false, 1),
+            (
+                &["clang-cl", "-Fdvc.pdb", "-Fo:", "a.obj", "a.c"],
+                Some("a.c"),
+                false,
+                1,
+            ),
+            (&["cl", "/O2", "/Tp", "a.c"], Some("a.c"), false, 2),
+            (&["ccache", "cc", "-O2", "a.c"], Some("a.c"), false, 3),
+            (&["-O2", "-Iinc"], None, true, 2),
+            (&["-O2", "-o", "a.o"], None, true, 1),
+            (&[], None, false, 0),
+            (&[], None, true, 0),
+        ];
+
+        for (args, file, flags, expected) in cases {
+            let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+            assert_eq!(
+                shared_prefix_len(&args, file.map(OsStr::new), *flags),
+                *expected,
+                "splitting {args:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn it_keeps_non_utf8_paths() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut entry = entry("a.cc");
+        entry.directory = PathBuf::from(OsStr::from_bytes(b"/proj/\xff"));
+        entry.file = SourceFile::File(PathBuf::from(OsStr::from_bytes(b"\xfe.cc")));
+
+        let compact: CompactDatabase = [&entry].into_iter().collect();
+        assert_eq!(compact.get(0), Some(entry));
+    }
+}
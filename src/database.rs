@@ -0,0 +1,527 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::de::{DeserializeOwned, Error as SerdeError, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::path::{normalize, resolve};
+use crate::{CompileArgs, CompileCommand, Error, SourceFile};
+
+/// Represents a `compile_commands.json` file
+///
+/// Entries are indexed by the resolved, lexically normalized path of their
+/// `file`, so looking up the commands for a source file does not require a
+/// linear scan. Entries derived from a `compile_flags.txt` file apply to every
+/// file, and are returned for any path without an entry of its own.
+///
+/// The database dereferences to a slice of its entries, and is serialized as a
+/// plain array of them.
+#[derive(Debug, Clone, Default)]
+pub struct CompilationDatabase {
+    entries: Vec<CompileCommand>,
+    /// Maps each resolved `file` path to the indices of its entries
+    index: HashMap<PathBuf, Vec<usize>>,
+    /// Indices of the `SourceFile::All` entries
+    fallback: Vec<usize>,
+}
+
+impl CompilationDatabase {
+    /// Creates an empty database
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a `compile_commands.json` file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid compilation
+    /// database, see [`CompilationDatabase::from_reader`]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads a compilation database from `reader`
+    ///
+    /// Unlike deserializing with `serde_json` directly, every entry is required to
+    /// have an `arguments` or `command` field, and errors identify the offending
+    /// entry and field
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails, the input is not valid JSON, or it does
+    /// not follow the compilation database format
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, Error> {
+        Self::from_json(serde_json::Deserializer::from_reader(reader))
+    }
+
+    /// Parses the entries one at a time, so only the entry being checked is
+    /// held as a `Value`
+    fn from_json<'de, R: serde_json::de::Read<'de>>(
+        mut deserializer: serde_json::Deserializer<R>,
+    ) -> Result<Self, Error> {
+        let db = (&mut deserializer).deserialize_any(EntriesVisitor)?;
+        deserializer.end()?;
+        db
+    }
+
+    /// Appends an entry, indexing it by its resolved `file` path
+    pub fn push(&mut self, entry: CompileCommand) {
+        let idx = self.entries.len();
+        match &entry.file {
+            SourceFile::All => self.fallback.push(idx),
+            SourceFile::File(file) => self
+                .index
+                .entry(resolve(&entry.directory, file))
+                .or_default()
+                .push(idx),
+        }
+        self.entries.push(entry);
+    }
+
+    /// Returns the first entry for `path`
+    ///
+    /// `path` is lexically normalized before the lookup. If no entry names `path`
+    /// as its `file`, the first `compile_flags.txt` derived entry is returned.
+    #[must_use]
+    pub fn get(&self, path: &Path) -> Option<&CompileCommand> {
+        self.get_all(path).next()
+    }
+
+    /// Returns every entry for `path`, in database order
+    ///
+    /// Like [`CompilationDatabase::get`], falls back to the `compile_flags.txt`
+    /// derived entries if no entry names `path` as its `file`
+    pub fn get_all(&self, path: &Path) -> impl Iterator<Item = &CompileCommand> {
+        let indices = self.index.get(&normalize(path)).unwrap_or(&self.fallback);
+        indices.iter().map(|&idx| &self.entries[idx])
+    }
+
+    /// Returns `true` if there is an entry for `path`
+    ///
+    /// See [`CompilationDatabase::get`]
+    #[must_use]
+    pub fn contains(&self, path: &Path) -> bool {
+        self.get(path).is_some()
+    }
+
+    /// Consumes the database, returning its entries
+    #[must_use]
+    pub fn into_vec(self) -> Vec<CompileCommand> {
+        self.entries
+    }
+}
+
+/// Visits the top level of a compilation database, checking each entry as
+/// soon as it is read. After the first invalid entry, the remaining entries
+/// are only checked for valid JSON.
+struct EntriesVisitor;
+
+impl EntriesVisitor {
+    fn not_an_array() -> Result<CompilationDatabase, Error> {
+        Err(Error::Schema {
+            index: None,
+            field: None,
+            message: String::from("expected an array of entries"),
+        })
+    }
+}
+
+impl<'de> Visitor<'de> for EntriesVisitor {
+    type Value = Result<CompilationDatabase, Error>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of entries")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut db = CompilationDatabase::new();
+        let mut index = 0;
+        while let Some(entry) = seq.next_element::<Value>()? {
+            match parse_entry(index, entry) {
+                Ok(entry) => db.push(entry),
+                Err(e) => {
+                    while seq.next_element::<IgnoredAny>()?.is_some() {}
+                    return Ok(Err(e));
+                }
+            }
+            index += 1;
+        }
+        Ok(Ok(db))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+        Ok(Self::not_an_array())
+    }
+
+    fn visit_bool<E: SerdeError>(self, _: bool) -> Result<Self::Value, E> {
+        Ok(Self::not_an_array())
+    }
+
+    fn visit_i64<E: SerdeError>(self, _: i64) -> Result<Self::Value, E> {
+        Ok(Self::not_an_array())
+    }
+
+    fn visit_u64<E: SerdeError>(self, _: u64) -> Result<Self::Value, E> {
+        Ok(Self::not_an_array())
+    }
+
+    fn visit_f64<E: SerdeError>(self, _: f64) -> Result<Self::Value, E> {
+        Ok(Self::not_an_array())
+    }
+
+    fn visit_str<E: SerdeError>(self, _: &str) -> Result<Self::Value, E> {
+        Ok(Self::not_an_array())
+    }
+
+    fn visit_unit<E: SerdeError>(self) -> Result<Self::Value, E> {
+        Ok(Self::not_an_array())
+    }
+}
+
+/// Checks the fields of a single entry, so that a failure can name the field
+/// responsible, then deserializes it
+pub(crate) fn parse_entry(index: usize, entry: Value) -> Result<CompileCommand, Error> {
+    type FieldCheck = fn(&Value) -> Result<(), String>;
+
+    fn check<T: DeserializeOwned>(value: &Value) -> Result<(), String> {
+        T::deserialize(value).map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    let schema_error = |field, message: String| Error::Schema {
+        index: Some(index),
+        field,
+        message,
+    };
+    let Value::Object(fields) = &entry else {
+        return Err(schema_error(None, String::from("expected an object")));
+    };
+
+    let checks: [(&'static str, bool, FieldCheck); 5] = [
+        ("directory", true, check::<PathBuf>),
+        ("file", true, check::<SourceFile>),
+        ("arguments", false, check::<CompileArgs>),
+        ("command", false, check::<String>),
+        ("output", false, check::<PathBuf>),
+    ];
+    for (field, required, check) in checks {
+        match fields.get(field) {
+            None | Some(Value::Null) if !required => {}
+            None => {
+                return Err(schema_error(
+                    Some(field),
+                    String::from("missing required field"),
+                ))
+            }
+            Some(value) => check(value).map_err(|message| schema_error(Some(field), message))?,
+        }
+    }
+
+    let has_command = |field| fields.get(field).is_some_and(|value| !value.is_null());
+    if !has_command("arguments") && !has_command("command") {
+        return Err(Error::MissingCommand { index });
+    }
+
+    CompileCommand::deserialize(entry).map_err(|e| schema_error(None, e.to_string()))
+}
+
+impl FromStr for CompilationDatabase {
+    type Err = Error;
+
+    /// Parses a compilation database, see [`CompilationDatabase::from_reader`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_json(serde_json::Deserializer::from_str(s))
+    }
+}
+
+impl Deref for CompilationDatabase {
+    type Target = [CompileCommand];
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+impl PartialEq for CompilationDatabase {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl Eq for CompilationDatabase {}
+
+impl From<Vec<CompileCommand>> for CompilationDatabase {
+    fn from(entries: Vec<CompileCommand>) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl From<CompilationDatabase> for Vec<CompileCommand> {
+    fn from(db: CompilationDatabase) -> Self {
+        db.entries
+    }
+}
+
+impl FromIterator<CompileCommand> for CompilationDatabase {
+    fn from_iter<I: IntoIterator<Item = CompileCommand>>(iter: I) -> Self {
+        let mut db = Self::new();
+        db.extend(iter);
+        db
+    }
+}
+
+impl Extend<CompileCommand> for CompilationDatabase {
+    fn extend<I: IntoIterator<Item = CompileCommand>>(&mut self, iter: I) {
+        for entry in iter {
+            self.push(entry);
+        }
+    }
+}
+
+impl IntoIterator for CompilationDatabase {
+    type Item = CompileCommand;
+    type IntoIter = std::vec::IntoIter<CompileCommand>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a CompilationDatabase {
+    type Item = &'a CompileCommand;
+    type IntoIter = std::slice::Iter<'a, CompileCommand>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+/// Writes the entries as a JSON array. Entries derived from `compile_flags.txt`
+/// are written in a form outside the JSON Compilation Database format, see
+/// [`CompileCommand::to_json_string`].
+impl Serialize for CompilationDatabase {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.entries.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CompilationDatabase {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<CompileCommand>::deserialize(deserializer).map(Self::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_compile_flags_txt, CompileArgs};
+
+    fn entry(directory: &str, file: &str, flag: &str) -> CompileCommand {
+        CompileCommand {
+            directory: PathBuf::from(directory),
+            file: SourceFile::File(PathBuf::from(file)),
+            arguments: Some(CompileArgs::Arguments(vec![
+                String::from("cc"),
+                String::from(flag),
+                String::from(file),
+            ])),
+            command: None,
+            output: None,
+        }
+    }
+
+    #[test]
+    fn it_looks_up_entries_by_resolved_path() {
+        let db = CompilationDatabase::from(vec![
+            entry("/proj/build", "../src/main.c", "-O0"),
+            entry("/proj/build", "/proj/src/util.c", "-O1"),
+            entry("/proj/build", "../src/./main.c", "-O2"),
+        ]);
+
+        assert_eq!(db.len(), 3);
+        assert_eq!(db.get(Path::new("/proj/src/main.c")), Some(&db[0]));
+        assert_eq!(db.get(Path::new("/proj/build/../src/util.c")), Some(&db[1]));
+        assert_eq!(
+            db.get_all(Path::new("/proj/src/main.c"))
+                .collect::<Vec<_>>(),
+            vec![&db[0], &db[2]]
+        );
+        assert!(db.contains(Path::new("/proj//src/main.c")));
+        assert!(!db.contains(Path::new("/proj/src/other.c")));
+        assert_eq!(db.get_all(Path::new("main.c")).count(), 0);
+    }
+
+    #[test]
+    fn it_falls_back_to_compile_flags_entries() {
+        let db = from_compile_flags_txt(Path::new("/proj"), "-Iinclude");
+
+        assert_eq!(db.get(Path::new("/proj/src/anything.c")), Some(&db[0]));
+        assert!(db.contains(Path::new("relative.c")));
+
+        let mut db = db;
+        db.push(entry("/proj", "main.c", "-O2"));
+        assert_eq!(db.get(Path::new("/proj/main.c")), Some(&db[1]));
+        assert_eq!(db.get(Path::new("/proj/other.c")), Some(&db[0]));
+    }
+
+    #[test]
+    fn it_loads_databases() {
+        let json = r#"[{"directory":"/proj","file":"main.c","arguments":["cc","-O2","main.c"]}]"#;
+
+        assert_eq!(
+            json.parse::<CompilationDatabase>().unwrap().into_vec(),
+            vec![entry("/proj", "main.c", "-O2")]
+        );
+        assert_eq!(
+            CompilationDatabase::from_reader(json.as_bytes())
+                .unwrap()
+                .into_vec(),
+            vec![entry("/proj", "main.c", "-O2")]
+        );
+    }
+
+    #[test]
+    fn it_reports_syntax_errors() {
+        let json = "[\n  {\"directory\": \"/proj\",,}\n]";
+
+        assert!(matches!(
+            json.parse::<CompilationDatabase>(),
+            Err(Error::Syntax {
+                line: 2,
+                column: 25,
+                ..
+            })
+        ));
+        // A syntax error after an invalid entry is still reported
+        assert!(matches!(
+            "[1, {]".parse::<CompilationDatabase>(),
+            Err(Error::Syntax {
+                line: 1,
+                column: 6,
+                ..
+            })
+        ));
+        assert!(matches!(
+            CompilationDatabase::from_reader(&b"[{"[..]),
+            Err(Error::Syntax {
+                line: 1,
+                column: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn it_reports_schema_errors() {
+        let cases: &[(&str, Option<usize>, Option<&str>)] = &[
+            (r#"{"directory": "/proj"}"#, None, None),
+            ("1", None, None),
+            (r#"[1]"#, Some(0), None),
+            (
+                r#"[{"file": "a.c", "command": "cc"}]"#,
+                Some(0),
+                Some("directory"),
+            ),
+            (
+                r#"[{"directory": "/proj", "command": "cc"}]"#,
+                Some(0),
+                Some("file"),
+            ),
+            (
+                r#"[{"directory": "/proj", "file": "a.c", "command": "cc"},
+                    {"directory": 1, "file": "b.c", "command": "cc"}]"#,
+                Some(1),
+                Some("directory"),
+            ),
+            (
+                r#"[{"directory": "/proj", "file": 2, "command": "cc"}]"#,
+                Some(0),
+                Some("file"),
+            ),
+            (
+                r#"[{"directory": "/proj", "file": "a.c", "arguments": ["cc", 3]}]"#,
+                Some(0),
+                Some("arguments"),
+            ),
+            (
+                r#"[{"directory": "/proj", "file": "a.c", "arguments": "cc a.c"}]"#,
+                Some(0),
+                Some("arguments"),
+            ),
+            (
+                r#"[{"directory": "/proj", "file": "a.c", "command": ["cc"]}]"#,
+                Some(0),
+                Some("command"),
+            ),
+            (
+                r#"[{"directory": "/proj", "file": "a.c", "command": "cc", "output": false}]"#,
+                Some(0),
+                Some("output"),
+            ),
+        ];
+
+        for (json, expected_index, expected_field) in cases {
+            match json.parse::<CompilationDatabase>() {
+                Err(Error::Schema { index, field, .. }) => {
+                    assert_eq!(index, *expected_index, "parsing {json}");
+                    assert_eq!(field, *expected_field, "parsing {json}");
+                }
+                result => panic!("expected a schema error parsing {json}, got {result:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn it_requires_a_command() {
+        let json = r#"[{"directory": "/proj", "file": "a.c", "command": "cc a.c"},
+                       {"directory": "/proj", "file": "b.c", "arguments": null}]"#;
+
+        let err = json.parse::<CompilationDatabase>().unwrap_err();
+        assert!(matches!(err, Error::MissingCommand { index: 1 }));
+        assert_eq!(
+            err.to_string(),
+            "entry 1: one of `arguments` or `command` is required"
+        );
+    }
+
+    #[test]
+    fn it_reports_io_errors() {
+        assert!(matches!(
+            CompilationDatabase::from_path("/does/not/exist/compile_commands.json"),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[test]
+    fn it_serializes_transparently() {
+        let json = r#"[{"directory":"/proj","file":"main.c","arguments":["cc","-O2","main.c"]}]"#;
+        let db = serde_json::from_str::<CompilationDatabase>(json).unwrap();
+
+        assert_eq!(db.into_vec(), vec![entry("/proj", "main.c", "-O2")]);
+        assert_eq!(
+            serde_json::to_string(&serde_json::from_str::<CompilationDatabase>(json).unwrap())
+                .unwrap(),
+            json
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{from_compile_flags_txt, CompilationDatabase, Error};
+
+/// The files searched for in each ancestor directory, in order, relative to
+/// that directory
+const SEARCH_PATHS: &[(DatabaseKind, &str)] = &[
+    (DatabaseKind::CompileCommands, "compile_commands.json"),
+    (DatabaseKind::CompileCommands, "build/compile_commands.json"),
+    (DatabaseKind::CompileFlags, "compile_flags.txt"),
+];
+
+/// The kind of file a [`DatabaseLocation`] refers to
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum DatabaseKind {
+    /// A `compile_commands.json` file
+    CompileCommands,
+    /// A `compile_flags.txt` file
+    CompileFlags,
+}
+
+impl DatabaseKind {
+    /// The name of the file this kind of database is stored in
+    #[must_use]
+    pub const fn file_name(self) -> &'static str {
+        match self {
+            Self::CompileCommands => "compile_commands.json",
+            Self::CompileFlags => "compile_flags.txt",
+        }
+    }
+}
+
+/// A compilation database found on disk
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct DatabaseLocation {
+    pub kind: DatabaseKind,
+    /// The path of the `compile_commands.json` or `compile_flags.txt` file
+    pub path: PathBuf,
+}
+
+impl DatabaseLocation {
+    /// The directory containing the database file
+    #[must_use]
+    pub fn directory(&self) -> &Path {
+        self.path.parent().unwrap_or_else(|| Path::new(""))
+    }
+
+    /// Reads and parses the database file
+    ///
+    /// The entry of a `compile_flags.txt` file uses the directory containing the
+    /// file as its `directory`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, or a `compile_commands.json`
+    /// file is not a valid database
+    pub fn load(&self) -> Result<CompilationDatabase, Error> {
+        match self.kind {
+            DatabaseKind::CompileCommands => CompilationDatabase::from_path(&self.path),
+            DatabaseKind::CompileFlags => {
+                let contents = fs::read_to_string(&self.path)?;
+                Ok(from_compile_flags_txt(self.directory(), &contents))
+            }
+        }
+    }
+}
+
+/// Finds the compilation database for a source file, mirroring clangd's search
+/// order
+///
+/// Starting from the directory containing the source file and moving up
+/// through its ancestors, each directory `dir` is searched for, in order:
+///
+/// 1. `dir/compile_commands.json`
+/// 2. `dir/build/compile_commands.json`
+/// 3. `dir/compile_flags.txt`
+///
+/// The result for every directory visited is cached, so later searches from
+/// files in the same or nested directories don't touch the filesystem again.
+#[derive(Debug, Clone, Default)]
+pub struct Discovery {
+    cache: HashMap<PathBuf, Option<DatabaseLocation>>,
+}
+
+impl Discovery {
+    /// Creates a searcher with an empty cache
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the database that applies to the file at `source`, if any
+    pub fn find(&mut self, source: &Path) -> Option<DatabaseLocation> {
+        let start = source.parent()?;
+        let mut visited = Vec::new();
+        let mut found = None;
+
+        for dir in start.ancestors() {
+            if let Some(cached) = self.cache.get(dir) {
+                found = cached.clone();
+                break;
+            }
+            visited.push(dir.to_path_buf());
+            if let Some(location) = search_dir(dir) {
+                found = Some(location);
+                break;
+            }
+        }
+
+        for dir in visited {
+            self.cache.insert(dir, found.clone());
+        }
+
+        found
+    }
+
+    /// Finds and loads the database that applies to the file at `source`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be loaded, see
+    /// [`DatabaseLocation::load`]
+    pub fn load(&mut self, source: &Path) -> Result<Option<CompilationDatabase>, Error> {
+        self.find(source)
+            .map(|location| location.load())
+            .transpose()
+    }
+
+    /// Forgets every cached result, e.g. after a database has been created or
+    /// removed
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+}
+
+/// Searches a single directory, without looking at its ancestors
+fn search_dir(dir: &Path) -> Option<DatabaseLocation> {
+    SEARCH_PATHS
+        .iter()
+        .map(|&(kind, path)| DatabaseLocation {
+            kind,
+            path: dir.join(path),
+        })
+        .find(|location| location.path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    const DB: &str = r#"[{"directory":"/proj/build","file":"../src/main.c","arguments":["cc","-c","../src/main.c"]}]"#;
+
+    #[test]
+    fn it_finds_compile_commands_in_ancestors() {
+        let tmp = TempDir::new("ancestors");
+        let db_path = tmp.write("compile_commands.json", DB);
+        let source = tmp.write("src/nested/main.c", "");
+
+        let mut discovery = Discovery::new();
+        let location = discovery.find(&source).unwrap();
+        assert_eq!(
+            location,
+            DatabaseLocation {
+                kind: DatabaseKind::CompileCommands,
+                path: db_path,
+            }
+        );
+        assert_eq!(location.directory(), tmp.path());
+        assert_eq!(location.load().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn it_follows_clangd_search_order() {
+        let tmp = TempDir::new("order");
+        let source = tmp.write("src/main.c", "");
+        tmp.write("build/compile_flags.txt", "-Iignored");
+
+        let mut discovery = Discovery::new();
+        assert_eq!(discovery.find(&source), None);
+
+        tmp.write("build/compile_commands.json", DB);
+        discovery.clear_cache();
+        assert_eq!(
+            discovery.find(&source).unwrap().path,
+            tmp.path().join("build/compile_commands.json")
+        );
+
+        // Flags files are only used if no JSON database is found
+        tmp.write("compile_flags.txt", "-Iinclude");
+        discovery.clear_cache();
+        assert_eq!(
+            discovery.find(&source).unwrap().path,
+            tmp.path().join("build/compile_commands.json")
+        );
+
+        tmp.write("compile_commands.json", DB);
+        discovery.clear_cache();
+        assert_eq!(
+            discovery.find(&source).unwrap().path,
+            tmp.path().join("compile_commands.json")
+        );
+
+        tmp.write("src/compile_flags.txt", "-DNEAREST");
+        discovery.clear_cache();
+        assert_eq!(
+            discovery.find(&source).unwrap().path,
+            tmp.path().join("src/compile_flags.txt")
+        );
+    }
+
+    #[test]
+    fn it_loads_compile_flags_with_their_directory() {
+        let tmp = TempDir::new("flags");
+        tmp.write("compile_flags.txt", "-xc++\n-Iinclude");
+        let source = tmp.write("main.cc", "");
+
+        let db = Discovery::new().load(&source).unwrap().unwrap();
+        assert_eq!(db[0].directory, tmp.path());
+        assert_eq!(*db[0].args().unwrap(), ["clang", "-xc++", "-Iinclude"]);
+    }
+
+    #[test]
+    fn it_caches_results_per_directory() {
+        let tmp = TempDir::new("cache");
+        let source = tmp.write("a/b/main.c", "");
+        let sibling = tmp.write("a/other.c", "");
+
+        let mut discovery = Discovery::new();
+        tmp.write("a/compile_flags.txt", "-DA");
+        assert!(discovery.find(&source).is_some());
+
+        // Removing the database is not noticed until the cache is cleared
+        fs::remove_file(tmp.path().join("a/compile_flags.txt")).unwrap();
+        assert!(discovery.find(&sibling).is_some());
+        discovery.clear_cache();
+        assert_eq!(
+            discovery
+                .find(&sibling)
+                .filter(|location| location.path.starts_with(tmp.path())),
+            None
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use crate::{ArgsError, CompileCommand};
+
+/// The kind of compiler driver named by `arguments[0]`
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Driver {
+    /// `gcc` or `cc`
+    Gcc,
+    /// `g++` or `c++`
+    Gxx,
+    /// `cpp` or `clang-cpp`, the C preprocessor
+    Cpp,
+    /// `clang`
+    Clang,
+    /// `clang++`
+    ClangXX,
+    /// `clang-cl`, clang with an MSVC compatible command line
+    ClangCl,
+    /// `cl.exe`, the MSVC compiler
+    Msvc,
+    /// `nvcc`, the CUDA compiler
+    Nvcc,
+    /// `as` or `gas`, the GNU assembler, or `llvm-mc`
+    GnuAs,
+    /// `nasm`
+    Nasm,
+    /// `yasm`
+    Yasm,
+    /// Any other program
+    Unknown,
+}
+
+impl Driver {
+    /// Returns `true` for drivers with an MSVC style command line
+    #[must_use]
+    pub const fn is_msvc_like(self) -> bool {
+        matches!(self, Self::Msvc | Self::ClangCl)
+    }
+
+    /// Returns `true` for the clang family of drivers
+    #[must_use]
+    pub const fn is_clang(self) -> bool {
+        matches!(self, Self::Clang | Self::ClangXX | Self::ClangCl)
+    }
+
+    /// Returns `true` for standalone assemblers
+    #[must_use]
+    pub const fn is_assembler(self) -> bool {
+        matches!(self, Self::GnuAs | Self::Nasm | Self::Yasm)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "gcc" | "cc" => Self::Gcc,
+            "g++" | "c++" => Self::Gxx,
+            "cpp" | "clang-cpp" => Self::Cpp,
+            "clang" => Self::Clang,
+            "clang++" => Self::ClangXX,
+            "clang-cl" => Self::ClangCl,
+            "cl" => Self::Msvc,
+            "nvcc" => Self::Nvcc,
+            "as" | "gas" | "llvm-mc" => Self::GnuAs,
+            "nasm" => Self::Nasm,
+            "yasm" => Self::Yasm,
+            _ => return None,
+        })
+    }
+}
+
+/// What could be learned about the compiler from its name and arguments
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct DriverInfo {
+    pub driver: Driver,
+    /// The target triple prefixed to the program name, as in
+    /// `aarch64-linux-gnu-gcc`
+    pub target: Option<String>,
+    /// The version suffixed to the program name, as in `gcc-12`
+    pub version: Option<String>,
+}
+
+/// The architectures a target triple prefix may start with
+const TRIPLE_ARCHES: &[&str] = &[
+    "x86_64",
+    "amd64",
+    "i386",
+    "i486",
+    "i586",
+    "i686",
+    "aarch64",
+    "arm64",
+    "arm",
+    "thumb",
+    "riscv32",
+    "riscv64",
+    "mips",
+    "powerpc",
+    "ppc",
+    "s390x",
+    "sparc",
+    "loongarch",
+    "wasm32",
+    "wasm64",
+    "avr",
+    "msp430",
+    "xtensa",
+    "hexagon",
+    "nvptx",
+];
+
+/// Strips a trailing `-<version>`, e.g. `-12` or `-17.0.1`
+fn split_version(name: &str) -> (&str, Option<&str>) {
+    match name.rsplit_once('-') {
+        Some((stem, version))
+            if !stem.is_empty()
+                && version.starts_with(|c: char| c.is_ascii_digit())
+                && version.chars().all(|c| c.is_ascii_digit() || c == '.') =>
+        {
+            (stem, Some(version))
+        }
+        _ => (name, None),
+    }
+}
+
+impl DriverInfo {
+    /// Classifies a program name such as `/usr/bin/clang++-17`, `CL.EXE` or
+    /// `aarch64-linux-gnu-gcc-12`
+    #[must_use]
+    pub fn from_program(program: &str) -> Self {
+        let name = program.rsplit(['/', '\\']).next().unwrap_or(program);
+        let name = name.to_ascii_lowercase();
+        let name = name.strip_suffix(".exe").unwrap_or(&name);
+        let (name, version) = split_version(name);
+        let version = version.map(ToString::to_string);
+
+        if let Some(driver) = Driver::from_name(name) {
+            return Self {
+                driver,
+                target: None,
+                version,
+            };
+        }
+
+        // Look for a known program name after a target triple
+        let prefixed = name.match_indices('-').find_map(|(i, _)| {
+            let (triple, driver) = (&name[..i], Driver::from_name(&name[i + 1..])?);
+            let is_triple =
+                triple.contains('-') && TRIPLE_ARCHES.iter().any(|arch| triple.starts_with(arch));
+            is_triple.then_some((triple, driver))
+        });
+        match prefixed {
+            Some((triple, driver)) => Self {
+                driver,
+                target: Some(triple.to_string()),
+                version,
+            },
+            None => Self {
+                driver: Driver::Unknown,
+                target: None,
+                version,
+            },
+        }
+    }
+
+    /// Classifies the compiler of an argument vector, where `args[0]` names the
+    /// compiler. A `--driver-mode=` argument overrides the mode of a clang
+    /// driver.
+    #[must_use]
+    pub fn from_args<S: AsRef<str>>(args: &[S]) -> Self {
+        let mut info = Self::from_program(args.first().map_or("", AsRef::as_ref));
+        let mode = args
+            .iter()
+            .skip(1)
+            .filter_map(|arg| arg.as_ref().strip_prefix("--driver-mode="))
+            .last();
+        if info.driver.is_clang() || info.driver == Driver::Unknown {
+            info.driver = match mode {
+                Some("gcc") => Driver::Clang,
+                Some("g++") => Driver::ClangXX,
+                Some("cpp") => Driver::Cpp,
+                Some("cl") => Driver::ClangCl,
+                _ => info.driver,
+            };
+        }
+        info
+    }
+}
+
+impl CompileCommand {
+    /// Classifies the entry's compiler, see [`DriverInfo::from_args`]. Launchers
+    /// such as `ccache` are skipped, see [`CompileCommand::launched_args`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument vector cannot be determined, see
+    /// [`CompileCommand::args`]
+    pub fn driver(&self) -> Result<DriverInfo, ArgsError> {
+        Ok(DriverInfo::from_args(&self.launched_args()?.compiler_args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_detects_drivers() {
+        let cases: &[(&str, Driver, Option<&str>, Option<&str>)] = &[
+            ("gcc", Driver::Gcc, None, None),
+            ("/usr/bin/cc", Driver::Gcc, None, None),
+            ("g++", Driver::Gxx, None, None),
+            ("c++", Driver::Gxx, None, None),
+            ("cpp", Driver::Cpp, None, None),
+            ("clang", Driver::Clang, None, None),
+            ("/usr/lib/llvm-17/bin/clang++", Driver::ClangXX, None, None),
+            ("clang++-17", Driver::ClangXX, None, Some("17")),
+            ("clang-cl", Driver::ClangCl, None, None),
+            (r"C:\LLVM\bin\clang-cl.exe", Driver::ClangCl, None, None),
+            ("cl.exe", Driver::Msvc, None, None),
+            ("CL.EXE", Driver::Msvc, None, None),
+            ("GCC", Driver::Gcc, None, None),
+            ("€ab", Driver::Unknown, None, None),
+            ("é.exe", Driver::Unknown, None, None),
+            (r"C:\VS\bin\Hostx64\x64\cl.exe", Driver::Msvc, None, None),
+            ("nvcc", Driver::Nvcc, None, None),
+            ("as", Driver::GnuAs, None, None),
+            ("nasm", Driver::Nasm, None, None),
+            ("yasm", Driver::Yasm, None, None),
+            ("gcc-12", Driver::Gcc, None, Some("12")),
+            ("gcc-12.2.0", Driver::Gcc, None, Some("12.2.0")),
+            (
+                "aarch64-linux-gnu-gcc-12",
+                Driver::Gcc,
+                Some("aarch64-linux-gnu"),
+                Some("12"),
+            ),
+            (
+                "/opt/cross/bin/arm-none-eabi-g++",
+                Driver::Gxx,
+                Some("arm-none-eabi"),
+                None,
+            ),
+            (
+                "x86_64-w64-mingw32-gcc.exe",
+                Driver::Gcc,
+                Some("x86_64-w64-mingw32"),
+                None,
+            ),
+            (
+                "riscv64-unknown-linux-gnu-as",
+                Driver::GnuAs,
+                Some("riscv64-unknown-linux-gnu"),
+                None,
+            ),
+            (
+                "x86_64-pc-linux-gnu-clang++-18",
+                Driver::ClangXX,
+                Some("x86_64-pc-linux-gnu"),
+                Some("18"),
+            ),
+            ("my-custom-gcc", Driver::Unknown, None, None),
+            ("clang-tidy", Driver::Unknown, None, None),
+            ("ccache", Driver::Unknown, None, None),
+            ("", Driver::Unknown, None, None),
+        ];
+
+        for (program, driver, target, version) in cases {
+            assert_eq!(
+                DriverInfo::from_program(program),
+                DriverInfo {
+                    driver: *driver,
+                    target: target.map(ToString::to_string),
+                    version: version.map(ToString::to_string),
+                },
+                "detecting {program:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_applies_driver_mode_overrides() {
+        let cases: &[(&[&str], Driver)] = &[
+            (&["clang", "--driver-mode=g++", "a.cc"], Driver::ClangXX),
+            (&["clang", "--driver-mode=cl", "a.cc"], Driver::ClangCl),
+            (&["clang++", "--driver-mode=gcc", "a.c"], Driver::Clang),
+            (&["clang", "--driver-mode=cpp"], Driver::Cpp),
+            (
+                &["clang", "--driver-mode=g++", "--driver-mode=cl"],
+                Driver::ClangCl,
+            ),
+            (&["gcc", "--driver-mode=cl"], Driver::Gcc),
+            (&["clang", "a.c"], Driver::Clang),
+        ];
+
+        for (args, driver) in cases {
+            assert_eq!(
+                DriverInfo::from_args(args).driver,
+                *driver,
+                "detecting {args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_detects_entry_drivers() {
+        let db = r#"[{"directory": "/p", "file": "a.c", "command": "\"C:\\LLVM\\bin\\clang.exe\" --driver-mode=cl /c a.c"}]"#
+            .parse::<crate::CompilationDatabase>()
+            .unwrap();
+
+        assert_eq!(db[0].driver().unwrap().driver, Driver::ClangCl);
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt::{self, Display};
+use std::io;
+
+/// Errors that can occur while loading a compilation database
+#[derive(Debug)]
+pub enum Error {
+    /// The database could not be read
+    Io(io::Error),
+    /// The database is not valid JSON
+    Syntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The database is valid JSON, but does not follow the compilation database
+    /// format
+    Schema {
+        /// The index of the offending entry, or `None` if the top level value
+        /// is not an array
+        index: Option<usize>,
+        /// The offending field, or `None` if the entry as a whole is malformed
+        field: Option<&'static str>,
+        message: String,
+    },
+    /// The entry at `index` has neither an `arguments` nor a `command` field
+    MissingCommand { index: usize },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "failed to read compilation database: {e}"),
+            Self::Syntax {
+                line,
+                column,
+                message,
+            } => write!(f, "invalid JSON at line {line}, column {column}: {message}"),
+            Self::Schema {
+                index,
+                field,
+                message,
+            } => {
+                match index {
+                    Some(index) => write!(f, "entry {index}")?,
+                    None => write!(f, "compilation database")?,
+                }
+                if let Some(field) = field {
+                    write!(f, ", field `{field}`")?;
+                }
+                write!(f, ": {message}")
+            }
+            Self::MissingCommand { index } => write!(
+                f,
+                "entry {index}: one of `arguments` or `command` is required"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Syntax { .. } | Self::Schema { .. } | Self::MissingCommand { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    /// Converts an error from parsing the JSON text itself. Errors from
+    /// interpreting the parsed value are reported as `Error::Schema` instead.
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_io() {
+            return Self::Io(e.into());
+        }
+
+        let message = e.to_string();
+        let location = format!(" at line {} column {}", e.line(), e.column());
+        Self::Syntax {
+            line: e.line(),
+            column: e.column(),
+            message: message
+                .strip_suffix(&location)
+                .unwrap_or(&message)
+                .to_string(),
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use crate::language::is_header;
+use crate::parsed::ValueKind;
+use crate::path::{normalize, resolve};
+use crate::{
+    CompilationDatabase, CompileArgs, CompileCommand, Dialect, Flag, Language, ParsedCommand,
+    SourceFile,
+};
+
+/// Flags that request dependency output without taking a value
+const DEPENDENCY_FLAGS: &[&str] = &["-M", "-MM", "-MD", "-MMD", "-MG", "-MP"];
+
+/// Classifies a file by extension, returning its language and whether it is a
+/// header
+fn classify(path: &Path) -> (Option<Language>, bool) {
+    let ext = path.extension().and_then(|ext| ext.to_str());
+    let language = match ext {
+        // A `.h` file's language is taken from the source file it borrows its
+        // command from
+        Some("h" | "inc") => None,
+        _ => Language::from_path(path),
+    };
+    (language, is_header(path))
+}
+
+/// Scores how good a template `candidate` is for `target`. Higher is better.
+fn score(target: &Path, candidate: &Path) -> (bool, usize, bool) {
+    let same_stem = target.file_stem() == candidate.file_stem();
+    let shared_dirs = target
+        .parent()
+        .unwrap_or(target)
+        .components()
+        .zip(candidate.parent().unwrap_or(candidate).components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (target_lang, _) = classify(target);
+    let (candidate_lang, _) = classify(candidate);
+    let same_lang = target_lang.is_some() && target_lang == candidate_lang;
+
+    (same_stem, shared_dirs, same_lang)
+}
+
+impl CompilationDatabase {
+    /// Returns the entry for `path`, or a command inferred from a similar entry
+    /// if the database has none, or else a `compile_flags.txt` derived entry
+    ///
+    /// See [`CompilationDatabase::infer`]
+    #[must_use]
+    pub fn get_or_infer(&self, path: &Path) -> Option<Cow<'_, CompileCommand>> {
+        let exact = self
+            .get(path)
+            .filter(|entry| !matches!(entry.file, SourceFile::All));
+        match exact {
+            Some(entry) => Some(Cow::Borrowed(entry)),
+            None => self
+                .infer(path)
+                .map(Cow::Owned)
+                .or_else(|| self.get(path).map(Cow::Borrowed)),
+        }
+    }
+
+    /// Synthesizes a command for `path`, a file without an entry of its own such
+    /// as a header, in the manner of clangd's `InterpolatingCompilationDatabase`
+    ///
+    /// Every entry is scored by its similarity to `path`: a matching file stem
+    /// (e.g. `foo.cc` for `foo.h`) counts most, then the number of leading
+    /// directories shared, then matching languages. The best entry's argument
+    /// vector is copied with its source file replaced by `path`, output and
+    /// dependency flags removed, and a `-x` flag added when `path` is a header
+    /// or in a different language than the entry's file. For MSVC style
+    /// commands, `/TC` or `/TP` is added instead.
+    ///
+    /// Returns `None` if the database has no entries for specific files
+    #[must_use]
+    pub fn infer(&self, path: &Path) -> Option<CompileCommand> {
+        let target = normalize(path);
+        let (best, best_file) = self
+            .iter()
+            .filter_map(|entry| Some((entry, entry.resolved_file()?)))
+            .fold(
+                None,
+                |best: Option<(&CompileCommand, PathBuf)>, candidate| match best {
+                    Some(best) if score(&target, &best.1) >= score(&target, &candidate.1) => {
+                        Some(best)
+                    }
+                    _ => Some(candidate),
+                },
+            )?;
+
+        let launched = best.launched_args().ok()?;
+        let parsed = ParsedCommand::parse(&launched.compiler_args);
+        let target_arg = target.to_string_lossy().into_owned();
+        let mut inferred = launched.launcher;
+        inferred.extend(parsed.program.iter().cloned());
+        let at = inferred.len();
+        let mut replaced_file = false;
+        for arg in &parsed.args {
+            let is_output = arg.value(parsed.dialect).is_some_and(|value| {
+                matches!(value.kind, ValueKind::Output | ValueKind::DependencyTarget)
+            });
+            let is_dependency =
+                matches!(arg.raw.as_slice(), [raw] if DEPENDENCY_FLAGS.contains(&raw.as_str()));
+            // Any language flag is dropped, and replaced below if needed
+            let is_language = matches!(arg.flag, Flag::Language(_) | Flag::ForceLanguage(_));
+            if is_output || is_dependency || is_language {
+                continue;
+            }
+
+            match &arg.flag {
+                Flag::Input(input) | Flag::TypedInput { path: input, .. }
+                    if resolve(&best.directory, Path::new(input)) == best_file =>
+                {
+                    inferred.push(target_arg.clone());
+                    replaced_file = true;
+                }
+                _ => inferred.extend(arg.raw.iter().cloned()),
+            }
+        }
+        if !replaced_file {
+            inferred.push(target_arg);
+        }
+
+        let (target_lang, is_header) = classify(&target);
+        let (best_lang, _) = classify(&best_file);
+        let lang = match (target_lang, is_header) {
+            (lang, true) => lang.or(best_lang).map(|lang| (lang, true)),
+            (Some(lang), false) if Some(lang) != best_lang => Some((lang, false)),
+            _ => None,
+        };
+        let lang_args = match (parsed.dialect, lang) {
+            (Dialect::Gcc, Some((lang, true))) => vec!["-x", lang.x_header_name()],
+            (Dialect::Gcc, Some((lang, false))) => vec!["-x", lang.x_name()],
+            (Dialect::Msvc, Some((Language::C, _))) => vec!["/TC"],
+            (Dialect::Msvc, Some((Language::Cxx, _))) => vec!["/TP"],
+            _ => Vec::new(),
+        };
+        inferred.splice(at..at, lang_args.into_iter().map(String::from));
+
+        Some(CompileCommand {
+            directory: best.directory.clone(),
+            file: SourceFile::File(target),
+            arguments: Some(CompileArgs::Arguments(inferred)),
+            command: None,
+            output: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DB: &str = r#"[
+  {"directory": "/proj/build", "file": "../src/net/socket.cc",
+   "arguments": ["clang++", "-Iinclude", "-DNET", "-MD", "-MF", "socket.d", "-c", "-o", "socket.o", "../src/net/socket.cc"]},
+  {"directory": "/proj/build", "file": "/proj/src/ui/window.cc",
+   "command": "clang++ -DUI -c -oobj/window.o /proj/src/ui/window.cc"},
+  {"directory": "/proj/build", "file": "/proj/src/ui/legacy.c",
+   "arguments": ["clang", "-DLEGACY", "-xc", "-c", "/proj/src/ui/legacy.c"]}
+]"#;
+
+    fn args(entry: &CompileCommand) -> Vec<&str> {
+        match &entry.arguments {
+            Some(CompileArgs::Arguments(args)) => args.iter().map(String::as_str).collect(),
+            _ => panic!("expected arguments"),
+        }
+    }
+
+    #[test]
+    fn it_prefers_matching_stems() {
+        let db = DB.parse::<CompilationDatabase>().unwrap();
+        let inferred = db.infer(Path::new("/proj/include/net/socket.h")).unwrap();
+
+        assert_eq!(inferred.directory, PathBuf::from("/proj/build"));
+        assert_eq!(
+            inferred.file,
+            SourceFile::File(PathBuf::from("/proj/include/net/socket.h"))
+        );
+        assert_eq!(
+            args(&inferred),
+            vec![
+                "clang++",
+                "-x",
+                "c++-header",
+                "-Iinclude",
+                "-DNET",
+                "-c",
+                "/proj/include/net/socket.h"
+            ]
+        );
+        assert_eq!(inferred.output, None);
+    }
+
+    #[test]
+    fn it_prefers_nearby_directories() {
+        let db = DB.parse::<CompilationDatabase>().unwrap();
+
+        let inferred = db.infer(Path::new("/proj/src/ui/button.cc")).unwrap();
+        assert_eq!(
+            args(&inferred),
+            vec!["clang++", "-DUI", "-c", "/proj/src/ui/button.cc"]
+        );
+
+        let inferred = db.infer(Path::new("/proj/src/ui/./colors.h")).unwrap();
+        assert_eq!(
+            args(&inferred),
+            vec![
+                "clang++",
+                "-x",
+                "c++-header",
+                "-DUI",
+                "-c",
+                "/proj/src/ui/colors.h"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_adjusts_the_language() {
+        let db = DB.parse::<CompilationDatabase>().unwrap();
+
+        let inferred = db.infer(Path::new("/proj/src/ui/compat.c")).unwrap();
+        assert_eq!(
+            args(&inferred),
+            vec!["clang", "-DLEGACY", "-c", "/proj/src/ui/compat.c"]
+        );
+
+        let inferred = db.infer(Path::new("/proj/src/ui/legacy.h")).unwrap();
+        assert_eq!(
+            args(&inferred),
+            vec![
+                "clang",
+                "-x",
+                "c-header",
+                "-DLEGACY",
+                "-c",
+                "/proj/src/ui/legacy.h"
+            ]
+        );
+
+        let inferred = db.infer(Path::new("/proj/src/net/bridge.mm")).unwrap();
+        assert_eq!(
+            args(&inferred),
+            vec![
+                "clang++",
+                "-x",
+                "objective-c++",
+                "-Iinclude",
+                "-DNET",
+                "-c",
+                "/proj/src/net/bridge.mm"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_drops_msvc_outputs() {
+        let db = r#"[{"directory": "/proj/build", "file": "/proj/src/win/win.cpp",
+          "arguments": ["cl.exe", "/nologo", "/Iinc", "/Foobj/win.obj", "/Fd:", "vc.pdb",
+                        "/Feout.exe", "/Tp", "/proj/src/win/win.cpp"]}]"#
+            .parse::<CompilationDatabase>()
+            .unwrap();
+
+        let inferred = db.infer(Path::new("/proj/src/win/win.h")).unwrap();
+        assert_eq!(
+            args(&inferred),
+            vec!["cl.exe", "/TP", "/nologo", "/Iinc", "/proj/src/win/win.h"]
+        );
+
+        let inferred = db.infer(Path::new("/proj/src/win/util.c")).unwrap();
+        assert_eq!(
+            args(&inferred),
+            vec!["cl.exe", "/TC", "/nologo", "/Iinc", "/proj/src/win/util.c"]
+        );
+    }
+
+    #[test]
+    fn it_prefers_exact_entries() {
+        let db = DB.parse::<CompilationDatabase>().unwrap();
+
+        assert!(matches!(
+            db.get_or_infer(Path::new("/proj/src/ui/window.cc")),
+            Some(Cow::Borrowed(entry)) if entry == &db[1]
+        ));
+        assert!(matches!(
+            db.get_or_infer(Path::new("/proj/src/ui/window.h")),
+            Some(Cow::Owned(_))
+        ));
+    }
+
+    #[test]
+    fn it_falls_back_to_compile_flags() {
+        let db = crate::from_compile_flags_txt(Path::new("/proj"), "-Iinclude");
+
+        assert_eq!(db.infer(Path::new("/proj/a.h")), None);
+        assert!(matches!(
+            db.get_or_infer(Path::new("/proj/a.h")),
+            Some(Cow::Borrowed(entry)) if entry == &db[0]
+        ));
+        assert_eq!(
+            CompilationDatabase::new().get_or_infer(Path::new("a.h")),
+            None
+        );
+    }
+}
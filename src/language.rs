@@ -0,0 +1,592 @@
+use std::cmp::Ordering;
+use std::path::Path;
+
+use crate::path::resolve;
+use crate::{ArgsError, CompileCommand, Driver, DriverInfo, Flag, ParsedCommand, SourceFile};
+
+/// The language a translation unit is written in
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Language {
+    C,
+    Cxx,
+    ObjC,
+    ObjCxx,
+    Cuda,
+    Hip,
+    OpenCl,
+    /// Assembly that is assembled as is, e.g. `.s`
+    Assembler,
+    /// Assembly that is run through the C preprocessor first, e.g. `.S`
+    AssemblerWithCpp,
+}
+
+impl Language {
+    /// Reads a `-x` language name, ignoring whether it names a header. Returns
+    /// `None` for `none` and unknown names.
+    #[must_use]
+    pub fn from_x(name: &str) -> Option<Self> {
+        let name = name.strip_suffix("-header").unwrap_or(name);
+        Some(match name {
+            "c" | "cpp-output" => Self::C,
+            "c++" | "c++-cpp-output" => Self::Cxx,
+            "objective-c" | "objc-cpp-output" => Self::ObjC,
+            "objective-c++" | "objc++-cpp-output" => Self::ObjCxx,
+            "cuda" | "cu" => Self::Cuda,
+            "hip" => Self::Hip,
+            "cl" | "clcpp" => Self::OpenCl,
+            "assembler" => Self::Assembler,
+            "assembler-with-cpp" => Self::AssemblerWithCpp,
+            _ => return None,
+        })
+    }
+
+    /// Guesses the language of a file from its extension, following clang,
+    /// where `.h` is a C header
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        extension(path)?.0
+    }
+
+    /// The `-x` name for a source file of this language
+    #[must_use]
+    pub const fn x_name(self) -> &'static str {
+        match self {
+            Self::C => "c",
+            Self::Cxx => "c++",
+            Self::ObjC => "objective-c",
+            Self::ObjCxx => "objective-c++",
+            Self::Cuda => "cuda",
+            Self::Hip => "hip",
+            Self::OpenCl => "cl",
+            Self::Assembler => "assembler",
+            Self::AssemblerWithCpp => "assembler-with-cpp",
+        }
+    }
+
+    /// The `-x` name for a header of this language. Languages without a header
+    /// type use their source name.
+    #[must_use]
+    pub const fn x_header_name(self) -> &'static str {
+        match self {
+            Self::C => "c-header",
+            Self::Cxx => "c++-header",
+            Self::ObjC => "objective-c-header",
+            Self::ObjCxx => "objective-c++-header",
+            _ => self.x_name(),
+        }
+    }
+
+    /// The language a C++ driver such as `g++` compiles a file of this language
+    /// as, since it treats C sources as C++
+    const fn as_cxx(self) -> Self {
+        match self {
+            Self::C => Self::Cxx,
+            Self::ObjC => Self::ObjCxx,
+            _ => self,
+        }
+    }
+}
+
+/// The known source and header extensions, with the language they suggest and
+/// whether they name a header
+const EXTENSIONS: &[(&str, Option<Language>, bool)] = &[
+    ("c", Some(Language::C), false),
+    ("i", Some(Language::C), false),
+    ("h", Some(Language::C), true),
+    ("cc", Some(Language::Cxx), false),
+    ("cpp", Some(Language::Cxx), false),
+    ("cxx", Some(Language::Cxx), false),
+    ("c++", Some(Language::Cxx), false),
+    ("cp", Some(Language::Cxx), false),
+    ("C", Some(Language::Cxx), false),
+    ("CC", Some(Language::Cxx), false),
+    ("CPP", Some(Language::Cxx), false),
+    ("cppm", Some(Language::Cxx), false),
+    ("ixx", Some(Language::Cxx), false),
+    ("ii", Some(Language::Cxx), false),
+    ("hh", Some(Language::Cxx), true),
+    ("hpp", Some(Language::Cxx), true),
+    ("hxx", Some(Language::Cxx), true),
+    ("h++", Some(Language::Cxx), true),
+    ("H", Some(Language::Cxx), true),
+    ("HPP", Some(Language::Cxx), true),
+    ("tcc", Some(Language::Cxx), true),
+    ("inl", Some(Language::Cxx), true),
+    ("ipp", Some(Language::Cxx), true),
+    ("m", Some(Language::ObjC), false),
+    ("mi", Some(Language::ObjC), false),
+    ("mm", Some(Language::ObjCxx), false),
+    ("M", Some(Language::ObjCxx), false),
+    ("mii", Some(Language::ObjCxx), false),
+    ("cu", Some(Language::Cuda), false),
+    ("cuh", Some(Language::Cuda), true),
+    ("hip", Some(Language::Hip), false),
+    ("cl", Some(Language::OpenCl), false),
+    ("s", Some(Language::Assembler), false),
+    ("asm", Some(Language::Assembler), false),
+    ("nasm", Some(Language::Assembler), false),
+    ("S", Some(Language::AssemblerWithCpp), false),
+    ("sx", Some(Language::AssemblerWithCpp), false),
+    ("inc", None, true),
+];
+
+/// Looks up the extension of `path` in [`EXTENSIONS`], returning the language
+/// it suggests and whether it names a header
+fn extension(path: &Path) -> Option<(Option<Language>, bool)> {
+    let ext = path.extension()?.to_str()?;
+    EXTENSIONS
+        .iter()
+        .find(|(name, _, _)| *name == ext)
+        .map(|&(_, language, header)| (language, header))
+}
+
+/// Returns `true` if `path` has a header extension
+pub(crate) fn is_header(path: &Path) -> bool {
+    extension(path).is_some_and(|(_, header)| header)
+}
+
+/// A C language standard, in chronological order
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord)]
+pub enum CStandard {
+    C89,
+    C99,
+    C11,
+    C17,
+    C23,
+    /// MSVC's `/std:clatest`
+    Latest,
+}
+
+/// A C++ language standard, in chronological order
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord)]
+pub enum CxxStandard {
+    Cxx98,
+    Cxx03,
+    Cxx11,
+    Cxx14,
+    Cxx17,
+    Cxx20,
+    Cxx23,
+    Cxx26,
+    /// MSVC's `/std:c++latest`
+    Latest,
+}
+
+/// A language standard given with `-std=` or `/std:`
+///
+/// Standards of the same language compare by age. C and C++ standards are not
+/// comparable with each other.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Standard {
+    C(CStandard),
+    Cxx(CxxStandard),
+}
+
+impl PartialOrd for Standard {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::C(a), Self::C(b)) => Some(a.cmp(b)),
+            (Self::Cxx(a), Self::Cxx(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+impl Standard {
+    /// Reads a `-std=` or `/std:` value, e.g. `c++17`, `gnu11` or `c++2a`. GNU
+    /// dialects are read as the standard they extend.
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        use CStandard as C;
+        use CxxStandard as Cxx;
+
+        if let Some(version) = name
+            .strip_prefix("c++")
+            .or_else(|| name.strip_prefix("gnu++"))
+        {
+            return Some(Self::Cxx(match version {
+                "98" => Cxx::Cxx98,
+                "03" => Cxx::Cxx03,
+                "11" | "0x" => Cxx::Cxx11,
+                "14" | "1y" => Cxx::Cxx14,
+                "17" | "1z" => Cxx::Cxx17,
+                "20" | "2a" => Cxx::Cxx20,
+                "23" | "2b" => Cxx::Cxx23,
+                "26" | "2c" => Cxx::Cxx26,
+                // MSVC's name for its partial C++23 support
+                "23preview" => Cxx::Cxx23,
+                "latest" => Cxx::Latest,
+                _ => return None,
+            }));
+        }
+
+        let version = name
+            .strip_prefix("gnu")
+            .or_else(|| name.strip_prefix('c'))
+            .or_else(|| name.strip_prefix("iso9899:"))?;
+        Some(Self::C(match version {
+            "89" | "90" | "1990" | "199409" => C::C89,
+            "99" | "9x" | "1999" => C::C99,
+            "11" | "1x" | "2011" => C::C11,
+            "17" | "18" | "2017" | "2018" => C::C17,
+            "23" | "2x" | "2024" => C::C23,
+            "latest" => C::Latest,
+            _ => return None,
+        }))
+    }
+}
+
+impl ParsedCommand {
+    /// Determines the language `input` is compiled as, where `input` is spelled
+    /// as in the argument vector. If `input` is `None` or isn't found, the
+    /// language of the command's last input is reported, using the last `-x`
+    /// given if `input` isn't found.
+    ///
+    /// The language is, in order of preference:
+    ///
+    /// 1. The last `-x` before `input`, unless it is `-x none`
+    /// 2. The language `input` was given with by `/Tc` or `/Tp`
+    /// 3. The language of every input given with `/TC` or `/TP`
+    /// 4. The language suggested by `input`'s extension, where C and
+    ///    Objective-C files are compiled as C++ and Objective-C++ by `g++`,
+    ///    `clang++` and `clang --driver-mode=g++`
+    #[must_use]
+    pub fn input_language(&self, input: Option<&str>) -> Option<Language> {
+        let forced = self
+            .args
+            .iter()
+            .filter_map(|arg| match arg.flag {
+                Flag::ForceLanguage(language) => Some(language),
+                _ => None,
+            })
+            .next_back();
+
+        // The input along with the `-x` language and `/Tc` or `/Tp` language it
+        // was given with
+        let mut x = None;
+        let mut last = None;
+        let mut found = None;
+        for arg in &self.args {
+            let (path, typed) = match &arg.flag {
+                Flag::Language(name) => {
+                    x = Language::from_x(name);
+                    continue;
+                }
+                Flag::Input(path) => (path.as_str(), None),
+                Flag::TypedInput { language, path } => (path.as_str(), Some(*language)),
+                _ => continue,
+            };
+            if input == Some(path) {
+                found = Some((path, x, typed));
+                break;
+            }
+            last = Some((path, x, typed));
+        }
+        let (path, x, typed) = match (found, input) {
+            (Some(found), _) => found,
+            (None, Some(input)) => (input, x, None),
+            (None, None) => last.unwrap_or(("", x, None)),
+        };
+
+        x.or(typed).or(forced).or_else(|| {
+            let language = Language::from_path(Path::new(path))?;
+            let driver = DriverInfo::from_args(&self.to_args());
+            match driver.driver {
+                Driver::Gxx | Driver::ClangXX => Some(language.as_cxx()),
+                _ => Some(language),
+            }
+        })
+    }
+
+    /// Returns the last language standard given, parsed
+    #[must_use]
+    pub fn parsed_standard(&self) -> Option<Standard> {
+        self.standard().and_then(Standard::parse)
+    }
+}
+
+/// Returns the spelling of the entry's `file` in `parsed`'s inputs, if it can
+/// be found
+pub(crate) fn file_input<'a>(
+    command: &CompileCommand,
+    parsed: &'a ParsedCommand,
+) -> Option<&'a str> {
+    let SourceFile::File(file) = &command.file else {
+        return None;
+    };
+    let file = resolve(&command.directory, file);
+    parsed
+        .inputs()
+        .find(|input| resolve(&command.directory, Path::new(input)) == file)
+}
+
+impl CompileCommand {
+    /// Determines the language the entry's `file` is compiled as, see
+    /// [`ParsedCommand::input_language`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument vector cannot be determined, see
+    /// [`CompileCommand::args`]
+    pub fn language(&self) -> Result<Option<Language>, ArgsError> {
+        let parsed = self.parse()?;
+        let language = match file_input(self, &parsed) {
+            Some(input) => parsed.input_language(Some(input)),
+            None => match &self.file {
+                SourceFile::File(file) => parsed.input_language(file.to_str()),
+                SourceFile::All => parsed.input_language(None),
+            },
+        };
+        Ok(language)
+    }
+
+    /// Returns the language standard the entry is compiled with, see
+    /// [`Standard::parse`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument vector cannot be determined, see
+    /// [`CompileCommand::args`]
+    pub fn standard(&self) -> Result<Option<Standard>, ArgsError> {
+        Ok(self.parse()?.parsed_standard())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompilationDatabase, Dialect};
+
+    #[test]
+    fn it_resolves_input_languages() {
+        let cases: &[(&[&str], Option<&str>, Option<Language>)] = &[
+            (&["gcc", "-c", "a.c"], Some("a.c"), Some(Language::C)),
+            (&["g++", "-c", "a.c"], Some("a.c"), Some(Language::Cxx)),
+            (
+                &["clang", "--driver-mode=g++", "-c", "a.c"],
+                Some("a.c"),
+                Some(Language::Cxx),
+            ),
+            (
+                &["clang++", "-c", "a.m"],
+                Some("a.m"),
+                Some(Language::ObjCxx),
+            ),
+            (
+                &["clang", "-c", "a.mm"],
+                Some("a.mm"),
+                Some(Language::ObjCxx),
+            ),
+            (&["nvcc", "-c", "k.cu"], Some("k.cu"), Some(Language::Cuda)),
+            (
+                &["hipcc", "-c", "k.hip"],
+                Some("k.hip"),
+                Some(Language::Hip),
+            ),
+            (
+                &["clang", "-c", "k.cl"],
+                Some("k.cl"),
+                Some(Language::OpenCl),
+            ),
+            (
+                &["gcc", "-c", "a.s"],
+                Some("a.s"),
+                Some(Language::Assembler),
+            ),
+            (
+                &["gcc", "-c", "a.S"],
+                Some("a.S"),
+                Some(Language::AssemblerWithCpp),
+            ),
+            (
+                &["gcc", "-x", "c++", "-c", "a.c"],
+                Some("a.c"),
+                Some(Language::Cxx),
+            ),
+            (
+                &["clang", "-xcuda", "a.cc"],
+                Some("a.cc"),
+                Some(Language::Cuda),
+            ),
+            (&["g++", "-x", "c", "a.c"], Some("a.c"), Some(Language::C)),
+            // `-x` only applies to the inputs after it
+            (
+                &["gcc", "a.c", "-x", "c++", "b.c"],
+                Some("a.c"),
+                Some(Language::C),
+            ),
+            (
+                &["gcc", "a.c", "-x", "c++", "b.c"],
+                Some("b.c"),
+                Some(Language::Cxx),
+            ),
+            (
+                &["gcc", "-x", "c++", "a.c", "-x", "none", "b.c"],
+                Some("b.c"),
+                Some(Language::C),
+            ),
+            (
+                &["gcc", "-x", "c++-header", "a.h"],
+                Some("a.h"),
+                Some(Language::Cxx),
+            ),
+            (&["gcc", "-c", "a.h"], Some("a.h"), Some(Language::C)),
+            (&["gcc", "-c", "a.unknown"], Some("a.unknown"), None),
+            (
+                &["gcc", "a.c", "-x", "c++", "b.c"],
+                None,
+                Some(Language::Cxx),
+            ),
+            (&["clang", "-xc++"], None, Some(Language::Cxx)),
+            (
+                &["cl.exe", "/c", "a.cpp"],
+                Some("a.cpp"),
+                Some(Language::Cxx),
+            ),
+            (
+                &["cl.exe", "/c", "/Tpa.c"],
+                Some("a.c"),
+                Some(Language::Cxx),
+            ),
+            (
+                &["cl.exe", "/c", "/Tc", "a.cpp"],
+                Some("a.cpp"),
+                Some(Language::C),
+            ),
+            (
+                &["cl.exe", "/c", "a.c", "/TP"],
+                Some("a.c"),
+                Some(Language::Cxx),
+            ),
+            (
+                &["clang-cl", "/TC", "-c", "a.cpp"],
+                Some("a.cpp"),
+                Some(Language::C),
+            ),
+            (
+                &["clang-cl", "/TC", "/Tpa.c"],
+                Some("a.c"),
+                Some(Language::Cxx),
+            ),
+        ];
+
+        for (args, input, language) in cases {
+            let parsed = ParsedCommand::parse(args);
+            assert_eq!(
+                parsed.input_language(*input),
+                *language,
+                "resolving {input:?} in {args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_parses_typed_msvc_inputs() {
+        let parsed = ParsedCommand::parse(&["cl.exe", "/Tpa.c", "/Tc", "b.cpp", "/TP"]);
+
+        assert_eq!(parsed.dialect, Dialect::Msvc);
+        assert_eq!(parsed.inputs().collect::<Vec<_>>(), ["a.c", "b.cpp"]);
+        assert_eq!(parsed.args[2].flag, Flag::ForceLanguage(Language::Cxx));
+        assert_eq!(
+            parsed.to_args(),
+            ["cl.exe", "/Tpa.c", "/Tc", "b.cpp", "/TP"]
+        );
+    }
+
+    #[test]
+    fn it_guesses_languages_from_extensions() {
+        let cases: &[(&str, Option<Language>, bool)] = &[
+            ("a.c", Some(Language::C), false),
+            ("a.h", Some(Language::C), true),
+            ("a.i", Some(Language::C), false),
+            ("a.ii", Some(Language::Cxx), false),
+            ("a.mi", Some(Language::ObjC), false),
+            ("a.mii", Some(Language::ObjCxx), false),
+            ("a.C", Some(Language::Cxx), false),
+            ("a.hpp", Some(Language::Cxx), true),
+            ("a.cuh", Some(Language::Cuda), true),
+            ("a.S", Some(Language::AssemblerWithCpp), false),
+            ("a.inc", None, true),
+            ("a.txt", None, false),
+            ("Makefile", None, false),
+        ];
+
+        for (path, language, header) in cases {
+            let path = Path::new(path);
+            assert_eq!(Language::from_path(path), *language, "guessing {path:?}");
+            assert_eq!(is_header(path), *header, "guessing {path:?}");
+        }
+    }
+
+    #[test]
+    fn it_parses_standards() {
+        let cases: &[(&str, Option<Standard>)] = &[
+            ("c89", Some(Standard::C(CStandard::C89))),
+            ("iso9899:1990", Some(Standard::C(CStandard::C89))),
+            ("gnu99", Some(Standard::C(CStandard::C99))),
+            ("c11", Some(Standard::C(CStandard::C11))),
+            ("c18", Some(Standard::C(CStandard::C17))),
+            ("c2x", Some(Standard::C(CStandard::C23))),
+            ("clatest", Some(Standard::C(CStandard::Latest))),
+            ("c++98", Some(Standard::Cxx(CxxStandard::Cxx98))),
+            ("c++0x", Some(Standard::Cxx(CxxStandard::Cxx11))),
+            ("gnu++14", Some(Standard::Cxx(CxxStandard::Cxx14))),
+            ("c++1z", Some(Standard::Cxx(CxxStandard::Cxx17))),
+            ("c++20", Some(Standard::Cxx(CxxStandard::Cxx20))),
+            ("c++2b", Some(Standard::Cxx(CxxStandard::Cxx23))),
+            ("c++26", Some(Standard::Cxx(CxxStandard::Cxx26))),
+            ("c++23preview", Some(Standard::Cxx(CxxStandard::Cxx23))),
+            ("c++latest", Some(Standard::Cxx(CxxStandard::Latest))),
+            ("c++42", None),
+            ("cuda", None),
+            ("", None),
+        ];
+
+        for (name, standard) in cases {
+            assert_eq!(Standard::parse(name), *standard, "parsing {name:?}");
+        }
+    }
+
+    #[test]
+    fn it_compares_standards() {
+        let c11 = Standard::C(CStandard::C11);
+        let c17 = Standard::C(CStandard::C17);
+        let cxx17 = Standard::Cxx(CxxStandard::Cxx17);
+        let cxx20 = Standard::Cxx(CxxStandard::Cxx20);
+
+        assert!(c11 < c17);
+        assert!(cxx20 > cxx17);
+        assert!(Standard::Cxx(CxxStandard::Latest) > cxx20);
+        assert_eq!(c17.partial_cmp(&cxx17), None);
+    }
+
+    #[test]
+    fn it_resolves_entry_languages() {
+        let db: CompilationDatabase = r#"[
+            {"directory": "/p", "file": "/p/src/a.c", "command": "g++ -std=gnu++17 -c src/a.c"},
+            {"directory": "/p", "file": "b.c", "command": "gcc -x c++ a.c -x none b.c"},
+            {"directory": "/p", "file": "c.cpp", "command": "cl.exe /std:c++latest /c c.cpp"},
+            {"directory": "/p", "file": "d.c", "arguments": ["clang", "-std=c11", "-c", "d.c"]}
+        ]"#
+        .parse()
+        .unwrap();
+        let expected = [
+            (Language::Cxx, Some(Standard::Cxx(CxxStandard::Cxx17))),
+            (Language::C, None),
+            (Language::Cxx, Some(Standard::Cxx(CxxStandard::Latest))),
+            (Language::C, Some(Standard::C(CStandard::C11))),
+        ];
+
+        for (entry, (language, standard)) in db.iter().zip(expected) {
+            assert_eq!(entry.language().unwrap(), Some(language), "reading {entry}");
+            assert_eq!(entry.standard().unwrap(), standard, "reading {entry}");
+        }
+
+        let flags = crate::from_compile_flags_txt(Path::new("/p"), "-xobjective-c\n-std=c99");
+        assert_eq!(flags[0].language().unwrap(), Some(Language::ObjC));
+        assert_eq!(
+            flags[0].standard().unwrap(),
+            Some(Standard::C(CStandard::C99))
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{ArgsError, CompileCommand};
+
+/// Wrapper programs that run the compiler named by their first argument
+const LAUNCHERS: &[&str] = &[
+    "ccache",
+    "sccache",
+    "distcc",
+    "icecc",
+    "icerun",
+    "buildcache",
+];
+
+/// An argument vector split into the launchers that wrap the compiler, such as
+/// `ccache` or `env CCACHE_DIR=/tmp`, and the compiler invocation itself
+#[derive(Debug, Clone, Default, Hash, Eq, PartialEq)]
+pub struct LaunchedArgs {
+    /// The launcher prefix, in the order it appeared
+    pub launcher: Vec<String>,
+    /// The effective argument vector, where `compiler_args[0]` is the compiler
+    pub compiler_args: Vec<String>,
+}
+
+/// Returns `true` for a `NAME=value` environment assignment
+fn is_assignment(arg: &str) -> bool {
+    arg.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Returns the number of leading arguments that make up one launcher, or 0 if
+/// `args` does not start with one
+fn launcher_len(args: &[String]) -> usize {
+    let Some(first) = args.first() else {
+        return 0;
+    };
+    if is_assignment(first) {
+        return 1;
+    }
+
+    let name = first.rsplit(['/', '\\']).next().unwrap_or(first);
+    let name = name.to_ascii_lowercase();
+    let name = name.strip_suffix(".exe").unwrap_or(&name);
+    let len = if name == "env" {
+        // `env [-i] [-u NAME] [NAME=value]... program`
+        let mut len = 1;
+        while let Some(arg) = args.get(len) {
+            len += match arg.as_str() {
+                "-i" | "--ignore-environment" | "-" => 1,
+                "-u" | "--unset" => 2,
+                _ if arg.starts_with("--unset=") || is_assignment(arg) => 1,
+                _ => break,
+            };
+        }
+        len
+    } else if LAUNCHERS.contains(&name) {
+        1
+    } else {
+        return 0;
+    };
+
+    // A launcher run without a compiler, such as `distcc -c a.c`, invokes a
+    // default compiler and is the compiler as far as we are concerned
+    match args.get(len) {
+        Some(next) if !next.starts_with('-') => len,
+        _ => 0,
+    }
+}
+
+impl LaunchedArgs {
+    /// Strips any chain of launchers from the front of `args`
+    #[must_use]
+    pub fn from_args(mut args: Vec<String>) -> Self {
+        let mut end = 0;
+        loop {
+            let len = launcher_len(&args[end..]);
+            if len == 0 {
+                break;
+            }
+            end += len;
+        }
+        let compiler_args = args.split_off(end);
+
+        Self {
+            launcher: args,
+            compiler_args,
+        }
+    }
+
+    /// Returns `true` if the compiler is run through a launcher
+    #[must_use]
+    pub fn has_launcher(&self) -> bool {
+        !self.launcher.is_empty()
+    }
+
+    /// Rejoins the launcher prefix and the compiler arguments into the argument
+    /// vector to execute
+    #[must_use]
+    pub fn restore(&self) -> Vec<String> {
+        self.launcher
+            .iter()
+            .chain(&self.compiler_args)
+            .cloned()
+            .collect()
+    }
+}
+
+impl CompileCommand {
+    /// Splits the entry's argument vector into its launchers and the compiler
+    /// invocation, see [`LaunchedArgs::from_args`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument vector cannot be determined, see
+    /// [`CompileCommand::args`]
+    pub fn launched_args(&self) -> Result<LaunchedArgs, ArgsError> {
+        Ok(LaunchedArgs::from_args(self.args()?.into_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn it_strips_launchers() {
+        let cases: &[(&[&str], usize)] = &[
+            (&["gcc", "-c", "a.c"], 0),
+            (&["ccache", "gcc", "-c", "a.c"], 1),
+            (&["/usr/bin/sccache", "clang++", "-c", "a.cc"], 1),
+            (&[r"C:\tools\sccache.exe", "cl.exe", "/c", "a.cc"], 1),
+            (&["ccache", "distcc", "gcc", "-c", "a.c"], 2),
+            (&["icecc", "g++", "-c", "a.cc"], 1),
+            (&["env", "CCACHE_DIR=/tmp/cc", "ccache", "gcc", "a.c"], 3),
+            (&["env", "-i", "-u", "LANG", "A=1", "gcc", "a.c"], 5),
+            (&["env", "gcc", "a.c"], 1),
+            (&["LANG=C", "ccache", "gcc", "a.c"], 2),
+            (&["distcc", "-c", "a.c"], 0),
+            (&["ccache"], 0),
+            (&["gcc", "ccache"], 0),
+            (&["env", "A=1"], 0),
+            (&["=x", "gcc"], 0),
+            (&[], 0),
+        ];
+
+        for (args, launcher_len) in cases {
+            let split = LaunchedArgs::from_args(strings(args));
+            assert_eq!(split.launcher, &args[..*launcher_len], "splitting {args:?}");
+            assert_eq!(
+                split.compiler_args,
+                &args[*launcher_len..],
+                "splitting {args:?}"
+            );
+            assert_eq!(split.has_launcher(), *launcher_len > 0);
+            assert_eq!(split.restore(), *args);
+        }
+    }
+
+    #[test]
+    fn it_restores_modified_arguments() {
+        let mut split = LaunchedArgs::from_args(strings(&["ccache", "gcc", "-c", "a.c"]));
+        split.compiler_args.push(String::from("-O2"));
+
+        assert_eq!(split.restore(), ["ccache", "gcc", "-c", "a.c", "-O2"]);
+    }
+
+    #[test]
+    fn it_sees_through_launchers() {
+        let db = r#"[{"directory": "/p", "file": "a.cc", "command": "ccache aarch64-linux-gnu-g++ -DX -c a.cc"}]"#
+            .parse::<crate::CompilationDatabase>()
+            .unwrap();
+
+        assert_eq!(db[0].launched_args().unwrap().launcher, ["ccache"]);
+        assert_eq!(db[0].driver().unwrap().driver, crate::Driver::Gxx);
+        let parsed = db[0].parse().unwrap();
+        assert_eq!(parsed.program.as_deref(), Some("aarch64-linux-gnu-g++"));
+        assert_eq!(parsed.defines().collect::<Vec<_>>(), [("X", None)]);
+    }
+}
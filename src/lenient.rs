@@ -0,0 +1,316 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::database::parse_entry;
+use crate::{tokenize_command, CompilationDatabase, Error};
+
+/// The repairs [`CompilationDatabase::from_str_lenient`] may make to malformed
+/// entries
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub struct LenientOptions {
+    /// Convert numbers and booleans to strings wherever a string is expected.
+    /// Defaults to `true`.
+    pub stringify_scalars: bool,
+    /// Accept `arguments` given as a single string, splitting it like
+    /// [`crate::CompileCommand::args_from_cmd`] splits `command`. Defaults to
+    /// `false`.
+    pub split_string_arguments: bool,
+}
+
+impl Default for LenientOptions {
+    fn default() -> Self {
+        Self {
+            stringify_scalars: true,
+            split_string_arguments: false,
+        }
+    }
+}
+
+/// The result of leniently loading a compilation database
+#[derive(Debug)]
+pub struct LenientLoad {
+    /// Every entry that could be read or repaired
+    pub database: CompilationDatabase,
+    /// The errors for the entries that were skipped, in entry order
+    pub errors: Vec<Error>,
+}
+
+impl CompilationDatabase {
+    /// Reads a `compile_commands.json` file, skipping or repairing malformed
+    /// entries instead of failing
+    ///
+    /// # Errors
+    ///
+    /// See [`CompilationDatabase::from_str_lenient`]
+    pub fn from_path_lenient<P: AsRef<Path>>(
+        path: P,
+        options: &LenientOptions,
+    ) -> Result<LenientLoad, Error> {
+        Self::from_str_lenient(&fs::read_to_string(path)?, options)
+    }
+
+    /// Parses a compilation database, skipping or repairing malformed entries
+    /// instead of failing
+    ///
+    /// Trailing commas in arrays and objects are ignored, and entries are
+    /// repaired according to `options`. Entries that still cannot be read are
+    /// skipped, and their errors are returned alongside the remaining entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input is not valid JSON even with trailing commas
+    /// removed, or the top level value is not an array
+    pub fn from_str_lenient(s: &str, options: &LenientOptions) -> Result<LenientLoad, Error> {
+        let value = serde_json::from_str(&blank_trailing_commas(s))?;
+        let Value::Array(entries) = value else {
+            return Err(Error::Schema {
+                index: None,
+                field: None,
+                message: String::from("expected an array of entries"),
+            });
+        };
+
+        let mut database = Self::new();
+        let mut errors = Vec::new();
+        for (index, mut entry) in entries.into_iter().enumerate() {
+            let parsed = match &mut entry {
+                Value::Object(fields) => repair_entry(index, fields, options),
+                _ => Ok(()),
+            }
+            .and_then(|()| parse_entry(index, entry));
+
+            match parsed {
+                Ok(entry) => database.push(entry),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        Ok(LenientLoad { database, errors })
+    }
+}
+
+/// Applies the repairs enabled in `options` to the fields of an entry
+fn repair_entry(
+    index: usize,
+    fields: &mut Map<String, Value>,
+    options: &LenientOptions,
+) -> Result<(), Error> {
+    if options.stringify_scalars {
+        for field in ["directory", "file", "command", "output"] {
+            if let Some(value) = fields.get_mut(field) {
+                stringify_scalar(value);
+            }
+        }
+        if let Some(Value::Array(args)) = fields.get_mut("arguments") {
+            args.iter_mut().for_each(stringify_scalar);
+        }
+    }
+
+    if options.split_string_arguments {
+        if let Some(Value::String(cmd)) = fields.get("arguments") {
+            let args = tokenize_command(cmd).map_err(|e| Error::Schema {
+                index: Some(index),
+                field: Some("arguments"),
+                message: e.to_string(),
+            })?;
+            fields.insert(
+                String::from("arguments"),
+                Value::Array(args.into_iter().map(Value::String).collect()),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn stringify_scalar(value: &mut Value) {
+    match value {
+        Value::Number(n) => *value = Value::String(n.to_string()),
+        Value::Bool(b) => *value = Value::String(b.to_string()),
+        _ => {}
+    }
+}
+
+/// Replaces commas that are directly followed, ignoring whitespace, by the end
+/// of an array or object with spaces, so that the positions reported in syntax
+/// errors match `s`
+fn blank_trailing_commas(s: &str) -> Cow<'_, str> {
+    let mut stripped = String::new();
+    let mut copied = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in s.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let next = s[i + 1..].trim_start_matches(|c: char| c.is_ascii_whitespace());
+            if next.starts_with([']', '}']) {
+                stripped.push_str(&s[copied..i]);
+                stripped.push(' ');
+                copied = i + 1;
+            }
+        }
+    }
+
+    if copied == 0 {
+        Cow::Borrowed(s)
+    } else {
+        stripped.push_str(&s[copied..]);
+        Cow::Owned(stripped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompileArgs, SourceFile};
+
+    #[test]
+    fn it_blanks_trailing_commas() {
+        let cases: &[(&str, &str)] = &[
+            ("[1, 2]", "[1, 2]"),
+            ("[1, 2,]", "[1, 2 ]"),
+            ("[1, 2 , \n ]", "[1, 2   \n ]"),
+            (r#"{"a": 1,}"#, r#"{"a": 1 }"#),
+            (r#"[{"a": [1,],},]"#, r#"[{"a": [1 ] } ]"#),
+            (r#"["a,]", "b\",}",]"#, r#"["a,]", "b\",}" ]"#),
+            (r#"["\\",]"#, r#"["\\" ]"#),
+        ];
+
+        for (json, expected) in cases {
+            assert_eq!(blank_trailing_commas(json), *expected, "blanking {json}");
+        }
+    }
+
+    #[test]
+    fn it_reports_positions_in_the_original_text() {
+        let json = "[[1,], [2,], tru]";
+
+        assert!(matches!(
+            CompilationDatabase::from_str_lenient(json, &LenientOptions::default()),
+            Err(Error::Syntax {
+                line: 1,
+                column: 17,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn it_skips_bad_entries() {
+        let json = r#"[
+            {"directory": "/p", "file": "a.c", "arguments": ["cc", "a.c"]},
+            {"directory": "/p", "file": "b.c"},
+            "not an entry",
+            {"directory": ["/p"], "file": "c.c", "command": "cc c.c"},
+            {"directory": "/p", "file": "d.c", "command": "cc d.c"},
+        ]"#;
+
+        let load = CompilationDatabase::from_str_lenient(json, &LenientOptions::default()).unwrap();
+        assert_eq!(load.database.len(), 2);
+        assert_eq!(load.database[0].file, SourceFile::File("a.c".into()));
+        assert_eq!(load.database[1].file, SourceFile::File("d.c".into()));
+        assert!(matches!(
+            load.errors.as_slice(),
+            [
+                Error::MissingCommand { index: 1 },
+                Error::Schema {
+                    index: Some(2),
+                    field: None,
+                    ..
+                },
+                Error::Schema {
+                    index: Some(3),
+                    field: Some("directory"),
+                    ..
+                },
+            ]
+        ));
+    }
+
+    #[test]
+    fn it_repairs_scalars() {
+        let json = r#"[{"directory": "/p", "file": 1, "arguments": ["cc", "-O", 2, true, "1"]}]"#;
+
+        let load = CompilationDatabase::from_str_lenient(json, &LenientOptions::default()).unwrap();
+        assert!(load.errors.is_empty());
+        assert_eq!(load.database[0].file, SourceFile::File("1".into()));
+        assert_eq!(
+            load.database[0].arguments,
+            Some(CompileArgs::Arguments(vec![
+                "cc".into(),
+                "-O".into(),
+                "2".into(),
+                "true".into(),
+                "1".into()
+            ]))
+        );
+
+        let options = LenientOptions {
+            stringify_scalars: false,
+            ..LenientOptions::default()
+        };
+        let load = CompilationDatabase::from_str_lenient(json, &options).unwrap();
+        assert!(load.database.is_empty());
+        assert_eq!(load.errors.len(), 1);
+    }
+
+    #[test]
+    fn it_splits_string_arguments() {
+        let json = r#"[
+            {"directory": "/p", "file": "a.c", "arguments": "cc \"-DX=a b\" a.c"},
+            {"directory": "/p", "file": "b.c", "arguments": "cc \"b.c"}
+        ]"#;
+
+        let load = CompilationDatabase::from_str_lenient(json, &LenientOptions::default()).unwrap();
+        assert!(load.database.is_empty());
+        assert_eq!(load.errors.len(), 2);
+
+        let options = LenientOptions {
+            split_string_arguments: true,
+            ..LenientOptions::default()
+        };
+        let load = CompilationDatabase::from_str_lenient(json, &options).unwrap();
+        assert_eq!(
+            load.database[0].arguments,
+            Some(CompileArgs::Arguments(vec![
+                "cc".into(),
+                "-DX=a b".into(),
+                "a.c".into()
+            ]))
+        );
+        assert!(matches!(
+            load.errors.as_slice(),
+            [Error::Schema {
+                index: Some(1),
+                field: Some("arguments"),
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn it_rejects_unrecoverable_input() {
+        let options = LenientOptions::default();
+
+        assert!(matches!(
+            CompilationDatabase::from_str_lenient("[{", &options),
+            Err(Error::Syntax { .. })
+        ));
+        assert!(matches!(
+            CompilationDatabase::from_str_lenient("{}", &options),
+            Err(Error::Schema { index: None, .. })
+        ));
+    }
+}
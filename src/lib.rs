@@ -3,14 +3,66 @@ use std::path::{Path, PathBuf};
 use std::string::ToString;
 
 use serde::de::{self, Deserializer, Error as SerdeError, Visitor};
-use serde::Deserialize;
-
-/// Represents a `compile_commands.json` file
-pub type CompilationDatabase = Vec<CompileCommand>;
+use serde::ser::{SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
+
+mod args;
+mod assembler;
+mod borrowed;
+mod compact;
+mod database;
+mod discovery;
+mod driver;
+mod error;
+mod interpolate;
+mod language;
+mod launcher;
+mod lenient;
+mod parsed;
+mod path;
+mod response;
+mod stream;
+mod target;
+#[cfg(test)]
+mod test_util;
+mod tokenize;
+mod validate;
+
+pub use args::{ArgsError, ArgsOptions, ConflictPolicy};
+pub use assembler::{AsmDialect, AsmInfo};
+pub use borrowed::{CompilationDatabaseRef, CompileCommandRef};
+pub use compact::{CompactDatabase, Interner, Symbol};
+pub use database::CompilationDatabase;
+pub use discovery::{DatabaseKind, DatabaseLocation, Discovery};
+pub use driver::{Driver, DriverInfo};
+pub use error::Error;
+pub use language::{CStandard, CxxStandard, Language, Standard};
+pub use launcher::LaunchedArgs;
+pub use lenient::{LenientLoad, LenientOptions};
+pub use parsed::{
+    AddressModel, Dialect, Flag, ForwardedArg, Forwarder, IncludeKind, ParsedArg, ParsedCommand,
+    Stage,
+};
+pub use path::{PathResolution, ResolveError};
+pub use response::{
+    expand_response_files, ResponseFileError, ResponseFileOptions, ResponseFileSyntax,
+};
+pub use stream::CommandStream;
+pub use target::{host_triple, Arch, TargetInfo, TargetSource};
+pub use tokenize::{
+    join_command, tokenize_command, tokenize_command_with, TokenizeError, TokenizeMode,
+};
+pub use validate::{Diagnostic, Severity};
 
 /// `All` if `CompilationDatabase` is generated from a `compile_flags.txt` file,
 /// otherwise `File()` containing the `file` field from a `compile_commands.json`
 /// entry
+///
+/// When serialized, `File()` is written as a string and `All` is written as `null`.
+/// A `null` `file` is an extension of this crate, not part of the JSON
+/// Compilation Database format: other tools reading such an entry will reject
+/// it. Only write `compile_flags.txt` derived entries to files this crate reads
+/// back.
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum SourceFile {
     All,
@@ -22,7 +74,6 @@ impl<'de> Deserialize<'de> for SourceFile {
     where
         D: Deserializer<'de>,
     {
-        #[allow(dead_code)]
         struct SourceFileVisitor;
 
         impl<'de> Visitor<'de> for SourceFileVisitor {
@@ -38,11 +89,34 @@ impl<'de> Deserialize<'de> for SourceFile {
             {
                 Ok(SourceFile::File(PathBuf::from(value)))
             }
+
+            fn visit_unit<E>(self) -> Result<Self::Value, E>
+            where
+                E: SerdeError,
+            {
+                Ok(SourceFile::All)
+            }
+
+            fn visit_none<E>(self) -> Result<Self::Value, E>
+            where
+                E: SerdeError,
+            {
+                Ok(SourceFile::All)
+            }
         }
 
-        match serde_json::Value::deserialize(deserializer)? {
-            serde_json::Value::String(s) => Ok(SourceFile::File(PathBuf::from(s))),
-            _ => Err(SerdeError::custom("expected a string")),
+        deserializer.deserialize_any(SourceFileVisitor)
+    }
+}
+
+impl Serialize for SourceFile {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            SourceFile::All => serializer.serialize_unit(),
+            SourceFile::File(file) => file.serialize(serializer),
         }
     }
 }
@@ -52,6 +126,12 @@ impl<'de> Deserialize<'de> for SourceFile {
 /// e.g. gcc @compile_flags.txt. Because the `CompileCommand` struct is used to
 /// represent both file types, we utilize a tagged union here to differentitate
 /// between the two files
+///
+/// Both variants are serialized as an array of strings. An `arguments` array is
+/// read back as `Flags()` when the enclosing entry's `file` is `SourceFile::All`.
+/// Unlike the format requires, a serialized `Flags()` array doesn't start with
+/// the compiler, so it can't be executed by other tools; see
+/// [`CompileCommand::args`] for an argument vector that can.
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum CompileArgs {
     Arguments(Vec<String>),
@@ -63,14 +143,13 @@ impl<'de> Deserialize<'de> for CompileArgs {
     where
         D: Deserializer<'de>,
     {
-        #[allow(dead_code)]
         struct CompileArgVisitor;
 
         impl<'de> Visitor<'de> for CompileArgVisitor {
             type Value = CompileArgs;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("a string representing a command line argument")
+                formatter.write_str("an array of strings representing command line arguments")
             }
 
             fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
@@ -91,12 +170,39 @@ impl<'de> Deserialize<'de> for CompileArgs {
     }
 }
 
+impl CompileArgs {
+    /// Renders the arguments as a `command` string that splits back into the same
+    /// arguments under `mode`
+    ///
+    /// See [`join_command`]
+    #[must_use]
+    pub fn to_command(&self, mode: TokenizeMode) -> String {
+        let (CompileArgs::Arguments(args) | CompileArgs::Flags(args)) = self;
+        join_command(args, mode)
+    }
+}
+
+impl Serialize for CompileArgs {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let (CompileArgs::Arguments(args) | CompileArgs::Flags(args)) = self;
+        let mut seq = serializer.serialize_seq(Some(args.len()))?;
+        for arg in args {
+            seq.serialize_element(arg)?;
+        }
+        seq.end()
+    }
+}
+
 /// Represents a single entry within a `compile_commands.json` file, or a compile_flags.txt file
 /// Either `arguments` or `command` is required. `arguments` is preferred, as shell (un)escaping
 /// is a possible source of errors.
 ///
 /// See: <https://clang.llvm.org/docs/JSONCompilationDatabase.html#format>
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(from = "RawCompileCommand")]
 pub struct CompileCommand {
     /// The working directory of the compilation. All paths specified in the `command`
     /// or `file` fields must be either absolute or relative to this directory.
@@ -110,101 +216,154 @@ pub struct CompileCommand {
     /// step for the translation unit file. arguments[0] should be the executable
     /// name, such as clang++. Arguments should not be escaped, but ready to pass
     /// to execvp().
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub arguments: Option<CompileArgs>,
     /// The compile command as a single shell-escaped string. Arguments may be
     /// shell quoted and escaped following platform conventions, with ‘"’ and ‘\’
     /// being the only special characters. Shell expansion is not supported.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub command: Option<String>,
     /// The name of the output created by this compilation step. This field is optional.
     /// It can be used to distinguish different processing modes of the same input
     /// file.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub output: Option<PathBuf>,
 }
 
-impl Display for CompileCommand {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "{{ \"directory\": \"{}\",", self.directory.display())?;
-
-        match &self.arguments {
-            Some(CompileArgs::Arguments(arguments)) => {
-                write!(f, "\"arguments\": [")?;
-                if arguments.is_empty() {
-                    writeln!(f, "],")?;
-                } else {
-                    for arg in arguments.iter().take(arguments.len() - 1) {
-                        writeln!(f, "\"{arg}\", ")?;
-                    }
-                    writeln!(f, "\"{}\"],", arguments[arguments.len() - 1])?;
-                }
-            }
-            Some(CompileArgs::Flags(flags)) => {
-                write!(f, "\"flags\": [")?;
-                if flags.is_empty() {
-                    writeln!(f, "],")?;
-                } else {
-                    for flag in flags.iter().take(flags.len() - 1) {
-                        writeln!(f, "\"{flag}\", ")?;
-                    }
-                    writeln!(f, "\"{}\"],", flags[flags.len() - 1])?;
-                }
-            }
-            None => {}
-        }
+/// The on-disk shape of a `CompileCommand`, before the `arguments` of a
+/// `compile_flags.txt` derived entry are restored to `CompileArgs::Flags`
+#[derive(Deserialize)]
+struct RawCompileCommand {
+    directory: PathBuf,
+    file: SourceFile,
+    arguments: Option<CompileArgs>,
+    command: Option<String>,
+    output: Option<PathBuf>,
+}
 
-        if let Some(command) = &self.command {
-            write!(f, "\"command\": \"{command}\"")?;
-        }
+impl From<RawCompileCommand> for CompileCommand {
+    fn from(raw: RawCompileCommand) -> Self {
+        let arguments = match (&raw.file, raw.arguments) {
+            (SourceFile::All, Some(CompileArgs::Arguments(flags))) => {
+                Some(CompileArgs::Flags(flags))
+            }
+            (_, arguments) => arguments,
+        };
 
-        if let Some(output) = &self.output {
-            writeln!(f, "\"output\": \"{}\"", output.display())?;
+        Self {
+            directory: raw.directory,
+            file: raw.file,
+            arguments,
+            command: raw.command,
+            output: raw.output,
         }
+    }
+}
 
-        match &self.file {
-            SourceFile::All => write!(f, "\"file\": all }}")?,
-            SourceFile::File(file) => write!(f, "\"file\": \"{}\" }}", file.display())?,
-        }
+/// Controls the whitespace emitted when writing a `CompileCommand` as JSON
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq)]
+pub enum JsonFormat {
+    /// A single line with no insignificant whitespace
+    #[default]
+    Compact,
+    /// Multiple lines with two space indentation
+    Pretty,
+}
 
-        Ok(())
+/// Writes the entry as a JSON object that deserializes back into an equal
+/// `CompileCommand`. The alternate flag (`{:#}`) selects `JsonFormat::Pretty`.
+///
+/// Paths that are not valid UTF-8 are written lossily, with invalid sequences
+/// replaced by `U+FFFD`. Use [`CompileCommand::to_json_string`] to reject them
+/// instead. Like `to_json_string`, `compile_flags.txt` derived entries are
+/// written in a form outside the JSON Compilation Database format.
+impl Display for CompileCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format = if f.alternate() {
+            JsonFormat::Pretty
+        } else {
+            JsonFormat::Compact
+        };
+        let json = match self.to_json_string(format) {
+            Ok(json) => json,
+            Err(_) => self
+                .to_lossy_utf8()
+                .to_json_string(format)
+                .map_err(|_| fmt::Error)?,
+        };
+        f.write_str(&json)
     }
 }
 
 impl CompileCommand {
+    /// Serializes the entry as a JSON object in the given `format`
+    ///
+    /// An entry derived from `compile_flags.txt` is written with a `null`
+    /// `file` and its flags as `arguments`, without a compiler. Neither is
+    /// valid in the JSON Compilation Database format, so only this crate can
+    /// read such an entry back. See [`SourceFile`] and [`CompileArgs`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `directory`, `file` or `output` is not valid UTF-8
+    pub fn to_json_string(&self, format: JsonFormat) -> serde_json::Result<String> {
+        match format {
+            JsonFormat::Compact => serde_json::to_string(self),
+            JsonFormat::Pretty => serde_json::to_string_pretty(self),
+        }
+    }
+
+    /// Returns a copy of the entry with its paths converted to UTF-8 lossily
+    fn to_lossy_utf8(&self) -> Self {
+        let lossy = |path: &Path| PathBuf::from(path.to_string_lossy().into_owned());
+
+        Self {
+            directory: lossy(&self.directory),
+            file: match &self.file {
+                SourceFile::All => SourceFile::All,
+                SourceFile::File(file) => SourceFile::File(lossy(file)),
+            },
+            arguments: self.arguments.clone(),
+            command: self.command.clone(),
+            output: self.output.as_deref().map(lossy),
+        }
+    }
+
     /// Transforms the command field, if present, into a `Vec<String>` of equivalent
     /// arguments
     ///
-    /// Replaces escaped '"' and '\' characters with their respective literals
-    pub fn args_from_cmd(&self) -> Option<Vec<String>> {
-        let escaped = if let Some(ref cmd) = self.command {
-            // "Arguments may be shell quoted and escaped following platform conventions,
-            // with ‘"’ and ‘\’ being the only special characters."
-            cmd.trim().replace("\\\\", "\\").replace("\\\"", "\"")
-        } else {
-            return None;
-        };
+    /// See [`tokenize_command`] for the quoting and escaping rules that are applied
+    pub fn args_from_cmd(&self) -> Option<Result<Vec<String>, TokenizeError>> {
+        self.command.as_deref().map(tokenize_command)
+    }
 
-        let mut args = Vec::new();
-        let mut start: usize = 0;
-        let mut end: usize = 0;
-        let mut in_quotes = false;
-
-        for c in escaped.chars() {
-            if c == '"' {
-                in_quotes = !in_quotes;
-                end += 1;
-            } else if c.is_whitespace() && !in_quotes && start != end {
-                args.push(escaped[start..end].to_string());
-                end += 1;
-                start = end;
-            } else {
-                end += 1;
-            }
-        }
+    /// Transforms the command field, if present, into a `Vec<String>` of equivalent
+    /// arguments, using the quoting rules of `mode`
+    ///
+    /// Use [`CompileCommand::tokenize_mode`] to split the command the way its
+    /// originating platform would
+    pub fn args_from_cmd_with(
+        &self,
+        mode: TokenizeMode,
+    ) -> Option<Result<Vec<String>, TokenizeError>> {
+        self.command
+            .as_deref()
+            .map(|cmd| tokenize_command_with(cmd, mode))
+    }
 
-        if start != end {
-            args.push(escaped[start..end].to_string());
-        }
+    /// Guesses the quoting convention of this entry from its program name, taken
+    /// from `arguments[0]` or the first word of `command`
+    ///
+    /// See [`TokenizeMode::detect`]
+    #[must_use]
+    pub fn tokenize_mode(&self) -> TokenizeMode {
+        let program = match (&self.arguments, &self.command) {
+            (Some(CompileArgs::Arguments(args)), _) => args.first().map_or("", String::as_str),
+            (_, Some(cmd)) => tokenize::program_name(cmd),
+            _ => "",
+        };
 
-        Some(args)
+        TokenizeMode::detect(program)
     }
 }
 
@@ -219,13 +378,13 @@ impl CompileCommand {
 #[must_use]
 pub fn from_compile_flags_txt(directory: &Path, contents: &str) -> CompilationDatabase {
     let args = CompileArgs::Flags(contents.lines().map(ToString::to_string).collect());
-    vec![CompileCommand {
+    CompilationDatabase::from(vec![CompileCommand {
         directory: directory.to_path_buf(),
         file: SourceFile::All,
         arguments: Some(args),
         command: None,
         output: None,
-    }]
+    }])
 }
 
 #[cfg(test)]
@@ -233,7 +392,7 @@ mod tests {
     use super::*;
 
     fn test_args_from_cmd(comp_cmd: &CompileCommand, expected_args: &Vec<&str>) {
-        let translated_args = comp_cmd.args_from_cmd().unwrap();
+        let translated_args = comp_cmd.args_from_cmd().unwrap().unwrap();
 
         assert!(expected_args.len() == translated_args.len());
         for (expected, actual) in expected_args.iter().zip(translated_args.iter()) {
@@ -262,7 +421,7 @@ mod tests {
             file: SourceFile::All,
             arguments: None,
             command: Some(String::from(
-                r#"/usr/bin/clang++ -Irelative -DSOMEDEF=\"With spaces, quotes and \\-es.\" -c -o file.o file.cc"#,
+                r#"/usr/bin/clang++ -Irelative -DSOMEDEF="With spaces, quotes and \\-es." -c -o file.o file.cc"#,
             )),
             output: None,
         };
@@ -270,7 +429,7 @@ mod tests {
         let expected_args: Vec<&str> = vec![
             "/usr/bin/clang++",
             "-Irelative",
-            r#"-DSOMEDEF="With spaces, quotes and \-es.""#,
+            r"-DSOMEDEF=With spaces, quotes and \-es.",
             "-c",
             "-o",
             "file.o",
@@ -278,4 +437,241 @@ mod tests {
         ];
         test_args_from_cmd(&comp_cmd, &expected_args);
     }
+
+    #[test]
+    fn it_translates_args_from_escaped_quotes_in_cmd() {
+        let comp_cmd = CompileCommand {
+            directory: PathBuf::new(),
+            file: SourceFile::All,
+            arguments: None,
+            command: Some(String::from(
+                r#"/usr/bin/clang++ -DSOMEDEF=\"With\ spaces\" -DPATH="\"C:\\inc\"" -c file.cc"#,
+            )),
+            output: None,
+        };
+
+        let expected_args: Vec<&str> = vec![
+            "/usr/bin/clang++",
+            r#"-DSOMEDEF="With spaces""#,
+            r#"-DPATH="C:\inc""#,
+            "-c",
+            "file.cc",
+        ];
+        test_args_from_cmd(&comp_cmd, &expected_args);
+    }
+
+    #[test]
+    fn it_reports_errors_from_cmd() {
+        let comp_cmd = CompileCommand {
+            directory: PathBuf::new(),
+            file: SourceFile::All,
+            arguments: None,
+            command: Some(String::from(r#"cc -DFOO="bar main.c"#)),
+            output: None,
+        };
+
+        assert_eq!(
+            comp_cmd.args_from_cmd(),
+            Some(Err(TokenizeError::UnterminatedQuote { position: 9 }))
+        );
+    }
+
+    #[test]
+    fn it_translates_args_from_sample_db() {
+        let comp_data = serde_json::from_str::<CompilationDatabase>(SAMPLE_DB).unwrap();
+        let Some(CompileArgs::Arguments(expected_args)) = &comp_data[0].arguments else {
+            panic!("expected an arguments entry");
+        };
+
+        assert_eq!(
+            comp_data[1].args_from_cmd().unwrap().unwrap(),
+            *expected_args
+        );
+    }
+
+    #[test]
+    fn it_translates_args_from_windows_cmd() {
+        let comp_cmd = CompileCommand {
+            directory: PathBuf::from(r"C:\proj\build"),
+            file: SourceFile::File(PathBuf::from(r"..\main.c")),
+            arguments: None,
+            command: Some(String::from(
+                r#"C:\PROGRA~1\LLVM\bin\clang-cl.exe /nologo "/IC:\Program Files\inc\\" /Fomain.obj /c ..\main.c"#,
+            )),
+            output: None,
+        };
+
+        assert_eq!(comp_cmd.tokenize_mode(), TokenizeMode::Windows);
+        assert_eq!(
+            comp_cmd
+                .args_from_cmd_with(comp_cmd.tokenize_mode())
+                .unwrap()
+                .unwrap(),
+            vec![
+                r"C:\PROGRA~1\LLVM\bin\clang-cl.exe",
+                "/nologo",
+                r"/IC:\Program Files\inc\",
+                "/Fomain.obj",
+                "/c",
+                r"..\main.c",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_renders_arguments_as_cmd() {
+        let comp_data = serde_json::from_str::<CompilationDatabase>(SAMPLE_DB).unwrap();
+        let arguments = comp_data[0].arguments.as_ref().unwrap();
+        let comp_cmd = CompileCommand {
+            arguments: None,
+            command: Some(arguments.to_command(TokenizeMode::Spec)),
+            ..comp_data[0].clone()
+        };
+
+        assert_eq!(
+            comp_cmd.command.as_deref(),
+            Some(
+                r#"/usr/bin/clang++ -Irelative "-DSOMEDEF=With spaces, quotes and \\-es." -c -o file.o file.cc"#
+            )
+        );
+        assert_eq!(
+            Some(CompileArgs::Arguments(
+                comp_cmd.args_from_cmd().unwrap().unwrap()
+            )),
+            comp_data[0].arguments
+        );
+    }
+
+    const SAMPLE_DB: &str = r#"[
+  { "directory": "/home/user/llvm/build",
+    "arguments": ["/usr/bin/clang++", "-Irelative", "-DSOMEDEF=With spaces, quotes and \\-es.", "-c", "-o", "file.o", "file.cc"],
+    "file": "file.cc" },
+
+  { "directory": "/home/user/llvm/build",
+    "command": "/usr/bin/clang++ -Irelative -DSOMEDEF=\"With spaces, quotes and \\\\-es.\" -c -o file.o file.cc",
+    "file": "file2.cc",
+    "output": "file.o" }
+]"#;
+
+    #[test]
+    fn it_round_trips_compile_commands_json() {
+        let comp_data = serde_json::from_str::<CompilationDatabase>(SAMPLE_DB).unwrap();
+        let serialized = serde_json::to_string(&comp_data).unwrap();
+        let reparsed = serde_json::from_str::<CompilationDatabase>(&serialized).unwrap();
+
+        assert_eq!(comp_data, reparsed);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(SAMPLE_DB).unwrap(),
+            serde_json::from_str::<serde_json::Value>(&serialized).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_omits_absent_optional_fields() {
+        let comp_cmd = CompileCommand {
+            directory: PathBuf::from("/build"),
+            file: SourceFile::File(PathBuf::from("main.c")),
+            arguments: Some(CompileArgs::Arguments(vec![
+                String::from("cc"),
+                String::from("main.c"),
+            ])),
+            command: None,
+            output: None,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&comp_cmd).unwrap(),
+            r#"{"directory":"/build","file":"main.c","arguments":["cc","main.c"]}"#
+        );
+    }
+
+    #[test]
+    fn it_round_trips_compile_flags_txt() {
+        let comp_data = from_compile_flags_txt(
+            &PathBuf::from("/home/user/proj"),
+            "-xc++\n-I\nlibwidget/include/",
+        );
+        let serialized = serde_json::to_string(&comp_data).unwrap();
+
+        assert_eq!(
+            serialized,
+            r#"[{"directory":"/home/user/proj","file":null,"arguments":["-xc++","-I","libwidget/include/"]}]"#
+        );
+        assert_eq!(
+            serde_json::from_str::<CompilationDatabase>(&serialized).unwrap(),
+            comp_data
+        );
+    }
+
+    #[test]
+    fn it_displays_parseable_json() {
+        let comp_cmd = CompileCommand {
+            directory: PathBuf::from("/home/user/llvm/build"),
+            file: SourceFile::File(PathBuf::from("file.cc")),
+            arguments: None,
+            command: Some(String::from(
+                r#"/usr/bin/clang++ -DFOO="x" -DBAR=\"y\" -c -o file.o file.cc"#,
+            )),
+            output: Some(PathBuf::from("file.o")),
+        };
+
+        for displayed in [format!("{comp_cmd}"), format!("{comp_cmd:#}")] {
+            let reparsed = serde_json::from_str::<CompileCommand>(&displayed).unwrap();
+            assert_eq!(comp_cmd, reparsed);
+        }
+        assert!(!format!("{comp_cmd}").contains('\n'));
+        assert!(format!("{comp_cmd:#}").contains('\n'));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn it_displays_non_utf8_paths_lossily() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let comp_cmd = CompileCommand {
+            directory: PathBuf::from("/proj"),
+            file: SourceFile::File(PathBuf::from(OsStr::from_bytes(b"a\xff.c"))),
+            arguments: Some(CompileArgs::Arguments(vec![String::from("cc")])),
+            command: None,
+            output: None,
+        };
+
+        assert!(comp_cmd.to_json_string(JsonFormat::Compact).is_err());
+        assert_eq!(
+            comp_cmd.to_string(),
+            r#"{"directory":"/proj","file":"a�.c","arguments":["cc"]}"#
+        );
+    }
+
+    #[test]
+    fn it_displays_compile_flags_entries() {
+        let comp_data = from_compile_flags_txt(&PathBuf::from("/proj"), "-xc++\n-DFOO=\"a b\"");
+        let displayed = comp_data[0].to_string();
+
+        assert_eq!(
+            displayed,
+            r#"{"directory":"/proj","file":null,"arguments":["-xc++","-DFOO=\"a b\""]}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<CompileCommand>(&displayed).unwrap(),
+            comp_data[0]
+        );
+    }
+
+    #[test]
+    fn it_writes_pretty_json() {
+        let comp_cmd = CompileCommand {
+            directory: PathBuf::from("/build"),
+            file: SourceFile::File(PathBuf::from("main.c")),
+            arguments: Some(CompileArgs::Arguments(vec![String::from("cc")])),
+            command: None,
+            output: None,
+        };
+
+        assert_eq!(
+            comp_cmd.to_json_string(JsonFormat::Pretty).unwrap(),
+            "{\n  \"directory\": \"/build\",\n  \"file\": \"main.c\",\n  \"arguments\": [\n    \"cc\"\n  ]\n}"
+        );
+    }
 }
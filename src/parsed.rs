@@ -0,0 +1,1596 @@
+use std::path::{Path, PathBuf};
+
+use crate::{ArgsError, CompileCommand, Driver, DriverInfo, Language};
+
+/// The search list an include directory is added to
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum IncludeKind {
+    /// `-iquote`, searched for `#include "..."` only
+    Quote,
+    /// `-I`, searched for both `#include "..."` and `#include <...>`
+    Angled,
+    /// `-isystem`, searched after `-I` and treated as a system directory
+    System,
+    /// `-idirafter`, searched after the standard system directories
+    After,
+}
+
+/// The address model selected by `-m32` or `-m64`
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum AddressModel {
+    Bits32,
+    Bits64,
+}
+
+/// The meaning of a single recognized argument
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum Flag {
+    /// `-I`, `-iquote`, `-isystem` or `-idirafter`
+    Include { kind: IncludeKind, path: PathBuf },
+    /// `-include`
+    ForceInclude(PathBuf),
+    /// `-D`, with the value following the first `=`, if any
+    Define { name: String, value: Option<String> },
+    /// `-U`
+    Undefine(String),
+    /// `-std=`
+    Standard(String),
+    /// `-x`
+    Language(String),
+    /// `--target=` or `-target`
+    Target(String),
+    /// `-march=`, or yasm's `-m`
+    Arch(String),
+    /// Apple clang's `-arch`
+    AppleArch(String),
+    /// `-mcpu=`
+    Cpu(String),
+    /// `-mabi=`
+    Abi(String),
+    /// `-m32` or `-m64`, or the GNU assembler's `--32` or `--64`
+    AddressModel(AddressModel),
+    /// `--sysroot`
+    Sysroot(PathBuf),
+    /// `-o`
+    Output(PathBuf),
+    /// nasm's and yasm's `-f`, e.g. `elf64`
+    OutputFormat(String),
+    /// The GNU assembler's `--defsym`, with the value following the first
+    /// `=`, if any
+    Symbol { name: String, value: Option<String> },
+    /// A positional argument, usually a source file
+    Input(String),
+    /// MSVC's `/Tc` or `/Tp`, an input compiled as C or C++ regardless of its
+    /// extension
+    TypedInput { language: Language, path: String },
+    /// MSVC's `/TC` or `/TP`, compiling every input as C or C++
+    ForceLanguage(Language),
+    /// Arguments passed through to another tool, such as `-Wp,-DFOO` or
+    /// `-Xclang -ast-dump`
+    Forwarded { via: Forwarder, args: Vec<String> },
+    /// Any argument that isn't recognized
+    Unknown,
+}
+
+/// The compilation stage a forwarded argument is passed to
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Stage {
+    Preprocessor,
+    /// The compiler proper, i.e. `clang -cc1`
+    Frontend,
+    Assembler,
+    Linker,
+}
+
+/// The driver flag an argument was forwarded with
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Forwarder {
+    /// `-Wp,<arg>,<arg>...`
+    Wp,
+    /// `-Wa,<arg>,<arg>...`
+    Wa,
+    /// `-Wl,<arg>,<arg>...`
+    Wl,
+    /// `-Xpreprocessor <arg>`
+    Xpreprocessor,
+    /// `-Xclang <arg>`
+    Xclang,
+    /// `-Xassembler <arg>`
+    Xassembler,
+    /// `-Xlinker <arg>`
+    Xlinker,
+}
+
+impl Forwarder {
+    /// The stage this flag forwards arguments to
+    #[must_use]
+    pub const fn stage(self) -> Stage {
+        match self {
+            Self::Wp | Self::Xpreprocessor => Stage::Preprocessor,
+            Self::Xclang => Stage::Frontend,
+            Self::Wa | Self::Xassembler => Stage::Assembler,
+            Self::Wl | Self::Xlinker => Stage::Linker,
+        }
+    }
+
+    /// The flag's spelling, e.g. `-Wp,` or `-Xclang`
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Wp => "-Wp,",
+            Self::Wa => "-Wa,",
+            Self::Wl => "-Wl,",
+            Self::Xpreprocessor => "-Xpreprocessor",
+            Self::Xclang => "-Xclang",
+            Self::Xassembler => "-Xassembler",
+            Self::Xlinker => "-Xlinker",
+        }
+    }
+
+    /// Spells the driver arguments that forward `args`, e.g. `["-Wp,-DA,-DB"]`
+    /// or `["-Xclang", "-DA", "-Xclang", "-DB"]`. Arguments containing a comma
+    /// cannot be forwarded with the `-W` flags, and are split at the comma by
+    /// the driver.
+    #[must_use]
+    pub fn pack<S: AsRef<str>>(self, args: &[S]) -> Vec<String> {
+        match self {
+            Self::Wp | Self::Wa | Self::Wl => {
+                let args: Vec<_> = args.iter().map(AsRef::as_ref).collect();
+                vec![format!("{}{}", self.name(), args.join(","))]
+            }
+            Self::Xpreprocessor | Self::Xclang | Self::Xassembler | Self::Xlinker => args
+                .iter()
+                .flat_map(|arg| [self.name().to_string(), arg.as_ref().to_string()])
+                .collect(),
+        }
+    }
+}
+
+/// The flags that forward arguments to another stage
+const FORWARDERS: &[Forwarder] = &[
+    Forwarder::Wp,
+    Forwarder::Wa,
+    Forwarder::Wl,
+    Forwarder::Xpreprocessor,
+    Forwarder::Xclang,
+    Forwarder::Xassembler,
+    Forwarder::Xlinker,
+];
+
+/// A recognized argument along with the argument strings it was parsed from
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct ParsedArg {
+    pub flag: Flag,
+    /// The original spelling, e.g. `["-Ifoo"]` or `["-I", "foo"]`
+    pub raw: Vec<String>,
+}
+
+/// How a flag's value may be spelled
+#[derive(Clone, Copy)]
+enum Arity {
+    /// `-Ifoo` or `-I foo`
+    JoinedOrSeparate,
+    /// `-target foo`
+    Separate,
+    /// `-std=foo`, where `=` is part of the flag's name
+    Joined,
+    /// `--sysroot=foo` or `--sysroot foo`
+    EqualsOrSeparate,
+}
+
+/// What a flag's value means to the passes that rewrite argument vectors, such
+/// as path resolution and command inference
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub(crate) enum ValueKind {
+    /// A path the compiler reads, such as `-I` or `--sysroot`
+    Path,
+    /// A file the compiler writes, such as `-o` or `-MF`
+    Output,
+    /// The target named in a dependency file, such as `-MT`
+    DependencyTarget,
+    /// Any other value
+    Other,
+}
+
+struct FlagSpec {
+    name: &'static str,
+    arity: Arity,
+    value: ValueKind,
+    make: fn(String) -> Flag,
+}
+
+const fn spec(name: &'static str, arity: Arity, make: fn(String) -> Flag) -> FlagSpec {
+    FlagSpec {
+        name,
+        arity,
+        value: ValueKind::Other,
+        make,
+    }
+}
+
+impl FlagSpec {
+    const fn path(mut self) -> Self {
+        self.value = ValueKind::Path;
+        self
+    }
+
+    const fn output(mut self) -> Self {
+        self.value = ValueKind::Output;
+        self
+    }
+
+    const fn dependency_target(mut self) -> Self {
+        self.value = ValueKind::DependencyTarget;
+        self
+    }
+}
+
+fn include(kind: IncludeKind, path: String) -> Flag {
+    Flag::Include {
+        kind,
+        path: PathBuf::from(path),
+    }
+}
+
+fn define(value: String) -> Flag {
+    match value.split_once('=') {
+        Some((name, value)) => Flag::Define {
+            name: name.to_string(),
+            value: Some(value.to_string()),
+        },
+        None => Flag::Define {
+            name: value,
+            value: None,
+        },
+    }
+}
+
+/// Defines a macro from the value of MSVC's `/D`, which also accepts `#` in
+/// place of `=`
+fn define_msvc(value: String) -> Flag {
+    match value.find(['=', '#']) {
+        Some(i) => Flag::Define {
+            name: value[..i].to_string(),
+            value: Some(value[i + 1..].to_string()),
+        },
+        None => Flag::Define {
+            name: value,
+            value: None,
+        },
+    }
+}
+
+/// The GCC style flags that take a value
+const GCC_FLAGS: &[FlagSpec] = &[
+    spec("-isystem", Arity::JoinedOrSeparate, |v| {
+        include(IncludeKind::System, v)
+    })
+    .path(),
+    spec("-isysroot", Arity::JoinedOrSeparate, |v| {
+        Flag::Sysroot(PathBuf::from(v))
+    })
+    .path(),
+    spec("-iquote", Arity::JoinedOrSeparate, |v| {
+        include(IncludeKind::Quote, v)
+    })
+    .path(),
+    spec("-idirafter", Arity::JoinedOrSeparate, |v| {
+        include(IncludeKind::After, v)
+    })
+    .path(),
+    spec("-iframework", Arity::JoinedOrSeparate, |_| Flag::Unknown).path(),
+    spec("-iprefix", Arity::JoinedOrSeparate, |_| Flag::Unknown).path(),
+    spec("-iwithprefixbefore", Arity::JoinedOrSeparate, |_| {
+        Flag::Unknown
+    }),
+    spec("-iwithprefix", Arity::JoinedOrSeparate, |_| Flag::Unknown),
+    spec("-imacros", Arity::JoinedOrSeparate, |_| Flag::Unknown).path(),
+    spec("-ivfsoverlay", Arity::JoinedOrSeparate, |_| Flag::Unknown).path(),
+    spec("-include-pch", Arity::Separate, |_| Flag::Unknown).path(),
+    spec("-include", Arity::JoinedOrSeparate, |v| {
+        Flag::ForceInclude(PathBuf::from(v))
+    })
+    .path(),
+    spec("-I", Arity::JoinedOrSeparate, |v| {
+        include(IncludeKind::Angled, v)
+    })
+    .path(),
+    spec("-F", Arity::JoinedOrSeparate, |_| Flag::Unknown).path(),
+    spec("-L", Arity::JoinedOrSeparate, |_| Flag::Unknown).path(),
+    spec("-l", Arity::JoinedOrSeparate, |_| Flag::Unknown),
+    spec("-D", Arity::JoinedOrSeparate, define),
+    spec("-U", Arity::JoinedOrSeparate, Flag::Undefine),
+    spec("-std=", Arity::Joined, Flag::Standard),
+    spec("--std=", Arity::Joined, Flag::Standard),
+    spec("-x", Arity::JoinedOrSeparate, Flag::Language),
+    spec("--target", Arity::EqualsOrSeparate, Flag::Target),
+    spec("-target", Arity::Separate, Flag::Target),
+    spec("--sysroot", Arity::EqualsOrSeparate, |v| {
+        Flag::Sysroot(PathBuf::from(v))
+    })
+    .path(),
+    spec("--gcc-toolchain", Arity::EqualsOrSeparate, |_| {
+        Flag::Unknown
+    })
+    .path(),
+    spec("-gcc-toolchain", Arity::Separate, |_| Flag::Unknown).path(),
+    spec("-resource-dir", Arity::EqualsOrSeparate, |_| Flag::Unknown).path(),
+    spec("-march=", Arity::Joined, Flag::Arch),
+    spec("-arch", Arity::Separate, Flag::AppleArch),
+    spec("-mcpu=", Arity::Joined, Flag::Cpu),
+    spec("-mabi=", Arity::Joined, Flag::Abi),
+    spec("-mllvm", Arity::Separate, |_| Flag::Unknown),
+    spec("--param", Arity::EqualsOrSeparate, |_| Flag::Unknown),
+    spec("-Xanalyzer", Arity::Separate, |_| Flag::Unknown),
+    spec("-Xarch_device", Arity::Separate, |_| Flag::Unknown),
+    spec("-Xarch_host", Arity::Separate, |_| Flag::Unknown),
+    spec("-z", Arity::Separate, |_| Flag::Unknown),
+    spec("-install_name", Arity::Separate, |_| Flag::Unknown),
+    spec("-MF", Arity::JoinedOrSeparate, |_| Flag::Unknown).output(),
+    spec("-MJ", Arity::JoinedOrSeparate, |_| Flag::Unknown).output(),
+    spec("-MT", Arity::JoinedOrSeparate, |_| Flag::Unknown).dependency_target(),
+    spec("-MQ", Arity::JoinedOrSeparate, |_| Flag::Unknown).dependency_target(),
+    spec("-dependency-file", Arity::Separate, |_| Flag::Unknown).output(),
+    spec("--serialize-diagnostics", Arity::Separate, |_| {
+        Flag::Unknown
+    })
+    .output(),
+    spec("-aux-info", Arity::Separate, |_| Flag::Unknown).output(),
+    // Flags spelled with a leading `-o`, which would otherwise be taken as an
+    // output joined to `-o`
+    spec("-objcmt-", Arity::Joined, |_| Flag::Unknown),
+    spec("-objc-", Arity::Joined, |_| Flag::Unknown),
+    spec("-object", Arity::Joined, |_| Flag::Unknown),
+    spec("-ohos", Arity::Joined, |_| Flag::Unknown),
+    spec("-o", Arity::JoinedOrSeparate, |v| {
+        Flag::Output(PathBuf::from(v))
+    })
+    .output(),
+];
+
+fn symbol(value: String) -> Flag {
+    match define(value) {
+        Flag::Define { name, value } => Flag::Symbol { name, value },
+        flag => flag,
+    }
+}
+
+/// The GNU assembler's options that take a value
+const GAS_FLAGS: &[FlagSpec] = &[
+    spec("--defsym", Arity::EqualsOrSeparate, symbol),
+    spec("-I", Arity::JoinedOrSeparate, |v| {
+        include(IncludeKind::Angled, v)
+    })
+    .path(),
+    spec("-march=", Arity::Joined, Flag::Arch),
+    spec("--MD", Arity::Separate, |_| Flag::Unknown).output(),
+    spec("-o", Arity::JoinedOrSeparate, |v| {
+        Flag::Output(PathBuf::from(v))
+    })
+    .output(),
+];
+
+/// nasm's options that take a value
+const NASM_FLAGS: &[FlagSpec] = &[
+    spec("-I", Arity::JoinedOrSeparate, |v| {
+        include(IncludeKind::Angled, v)
+    })
+    .path(),
+    spec("-i", Arity::JoinedOrSeparate, |v| {
+        include(IncludeKind::Angled, v)
+    })
+    .path(),
+    spec("-P", Arity::JoinedOrSeparate, |v| {
+        Flag::ForceInclude(PathBuf::from(v))
+    })
+    .path(),
+    spec("-p", Arity::JoinedOrSeparate, |v| {
+        Flag::ForceInclude(PathBuf::from(v))
+    })
+    .path(),
+    spec("-D", Arity::JoinedOrSeparate, define),
+    spec("-d", Arity::JoinedOrSeparate, define),
+    spec("-U", Arity::JoinedOrSeparate, Flag::Undefine),
+    spec("-u", Arity::JoinedOrSeparate, Flag::Undefine),
+    spec("-f", Arity::JoinedOrSeparate, Flag::OutputFormat),
+    spec("-F", Arity::JoinedOrSeparate, |_| Flag::Unknown),
+    spec("-X", Arity::JoinedOrSeparate, |_| Flag::Unknown),
+    spec("-Z", Arity::JoinedOrSeparate, |_| Flag::Unknown).output(),
+    spec("--prefix", Arity::Separate, |_| Flag::Unknown),
+    spec("--postfix", Arity::Separate, |_| Flag::Unknown),
+    spec("--gprefix", Arity::Separate, |_| Flag::Unknown),
+    spec("--gpostfix", Arity::Separate, |_| Flag::Unknown),
+    spec("--lprefix", Arity::Separate, |_| Flag::Unknown),
+    spec("--lpostfix", Arity::Separate, |_| Flag::Unknown),
+    spec("--pragma", Arity::Separate, |_| Flag::Unknown),
+    spec("--before", Arity::Separate, |_| Flag::Unknown),
+    spec("-MF", Arity::Separate, |_| Flag::Unknown).output(),
+    spec("-MT", Arity::Separate, |_| Flag::Unknown).dependency_target(),
+    spec("-MQ", Arity::Separate, |_| Flag::Unknown).dependency_target(),
+    spec("-l", Arity::JoinedOrSeparate, |_| Flag::Unknown).output(),
+    spec("-o", Arity::JoinedOrSeparate, |v| {
+        Flag::Output(PathBuf::from(v))
+    })
+    .output(),
+];
+
+/// yasm's options that take a value
+const YASM_FLAGS: &[FlagSpec] = &[
+    spec("-I", Arity::JoinedOrSeparate, |v| {
+        include(IncludeKind::Angled, v)
+    })
+    .path(),
+    spec("-P", Arity::JoinedOrSeparate, |v| {
+        Flag::ForceInclude(PathBuf::from(v))
+    })
+    .path(),
+    spec("-D", Arity::JoinedOrSeparate, define),
+    spec("-U", Arity::JoinedOrSeparate, Flag::Undefine),
+    spec("--oformat", Arity::EqualsOrSeparate, Flag::OutputFormat),
+    spec("-f", Arity::JoinedOrSeparate, Flag::OutputFormat),
+    spec("--machine", Arity::EqualsOrSeparate, Flag::Arch),
+    spec("-m", Arity::JoinedOrSeparate, Flag::Arch),
+    spec("--parser", Arity::EqualsOrSeparate, |_| Flag::Unknown),
+    spec("-p", Arity::JoinedOrSeparate, |_| Flag::Unknown),
+    spec("--preproc", Arity::EqualsOrSeparate, |_| Flag::Unknown),
+    spec("-r", Arity::JoinedOrSeparate, |_| Flag::Unknown),
+    spec("--dformat", Arity::EqualsOrSeparate, |_| Flag::Unknown),
+    spec("-g", Arity::JoinedOrSeparate, |_| Flag::Unknown),
+    spec("--lformat", Arity::EqualsOrSeparate, |_| Flag::Unknown),
+    spec("-L", Arity::JoinedOrSeparate, |_| Flag::Unknown),
+    spec("-X", Arity::JoinedOrSeparate, |_| Flag::Unknown),
+    spec("--prefix", Arity::EqualsOrSeparate, |_| Flag::Unknown),
+    spec("--suffix", Arity::EqualsOrSeparate, |_| Flag::Unknown),
+    spec("--error-file", Arity::EqualsOrSeparate, |_| Flag::Unknown).output(),
+    spec("-E", Arity::JoinedOrSeparate, |_| Flag::Unknown).output(),
+    spec("--list", Arity::EqualsOrSeparate, |_| Flag::Unknown).output(),
+    spec("-l", Arity::JoinedOrSeparate, |_| Flag::Unknown).output(),
+    spec("--objfile", Arity::EqualsOrSeparate, |v| {
+        Flag::Output(PathBuf::from(v))
+    })
+    .output(),
+    spec("-o", Arity::JoinedOrSeparate, |v| {
+        Flag::Output(PathBuf::from(v))
+    })
+    .output(),
+];
+
+/// The MSVC style options that take a value, named without their `/` or `-`
+/// prefix
+const MSVC_FLAGS: &[FlagSpec] = &[
+    spec("external:I", Arity::JoinedOrSeparate, |v| {
+        include(IncludeKind::System, v)
+    })
+    .path(),
+    spec("imsvc", Arity::JoinedOrSeparate, |v| {
+        include(IncludeKind::System, v)
+    })
+    .path(),
+    spec("I", Arity::JoinedOrSeparate, |v| {
+        include(IncludeKind::Angled, v)
+    })
+    .path(),
+    spec("D", Arity::JoinedOrSeparate, define_msvc),
+    spec("U", Arity::JoinedOrSeparate, Flag::Undefine),
+    spec("FI", Arity::JoinedOrSeparate, |v| {
+        Flag::ForceInclude(PathBuf::from(v))
+    })
+    .path(),
+    spec("std:", Arity::Joined, Flag::Standard),
+    spec("Tc", Arity::JoinedOrSeparate, |path| Flag::TypedInput {
+        language: Language::C,
+        path,
+    }),
+    spec("Tp", Arity::JoinedOrSeparate, |path| Flag::TypedInput {
+        language: Language::Cxx,
+        path,
+    }),
+    spec("Fo:", Arity::JoinedOrSeparate, |v| {
+        Flag::Output(PathBuf::from(v))
+    })
+    .output(),
+    spec("Fo", Arity::Joined, |v| Flag::Output(PathBuf::from(v))).output(),
+    spec("Fe:", Arity::JoinedOrSeparate, |_| Flag::Unknown).output(),
+    spec("Fe", Arity::Joined, |_| Flag::Unknown).output(),
+    spec("Fd:", Arity::JoinedOrSeparate, |_| Flag::Unknown).output(),
+    spec("Fd", Arity::Joined, |_| Flag::Unknown).output(),
+    spec("Fp:", Arity::JoinedOrSeparate, |_| Flag::Unknown).output(),
+    spec("Fp", Arity::Joined, |_| Flag::Unknown).output(),
+    spec("Fa:", Arity::JoinedOrSeparate, |_| Flag::Unknown).output(),
+    spec("Fa", Arity::Joined, |_| Flag::Unknown).output(),
+    spec("Fi:", Arity::JoinedOrSeparate, |_| Flag::Unknown).output(),
+    spec("Fi", Arity::Joined, |_| Flag::Unknown).output(),
+];
+
+/// The command line syntax of a compiler driver
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq)]
+pub enum Dialect {
+    /// GCC and clang style flags, such as `-Ifoo`
+    #[default]
+    Gcc,
+    /// `cl.exe` and `clang-cl` style options, such as `/Ifoo` or `-Ifoo`
+    Msvc,
+    /// The GNU assembler's options, such as `--defsym` and `--64`
+    Gas,
+    /// nasm's options, such as `-f elf64` and `-dNAME`
+    Nasm,
+    /// yasm's options, such as `-f elf64` and `-p gas`
+    Yasm,
+}
+
+impl Dialect {
+    /// Detects the dialect of an argument vector from its driver, see
+    /// [`DriverInfo::from_args`]
+    #[must_use]
+    pub fn detect<S: AsRef<str>>(args: &[S]) -> Self {
+        match DriverInfo::from_args(args).driver {
+            Driver::Msvc | Driver::ClangCl => Self::Msvc,
+            Driver::GnuAs => Self::Gas,
+            Driver::Nasm => Self::Nasm,
+            Driver::Yasm => Self::Yasm,
+            _ => Self::Gcc,
+        }
+    }
+}
+
+/// A compile command's argument vector, split into recognized flags
+///
+/// Recognizes the joined and separate forms of include directories
+/// (`-I`, `-iquote`, `-isystem`, `-idirafter`, `-include`), macro definitions
+/// (`-D`, `-U`), the language (`-x`) and its standard (`-std=`), the target
+/// (`--target=`, `-target`, `-arch`, `-m32`, `-m64`, `-march=`, `-mcpu=`,
+/// `-mabi=`, `--sysroot`) and the output (`-o`).
+/// Argument order is preserved, and unrecognized arguments are kept as
+/// `Flag::Unknown` with their original spelling.
+///
+/// Entries run by `cl.exe` or `clang-cl` are parsed as MSVC style options,
+/// recognizing `/I`, `/external:I`, `-imsvc`, `/D`, `/U`, `/FI`, `/std:`,
+/// `/Tc`, `/Tp`, `/TC`, `/TP` and `/Fo` with either a `/` or `-` prefix.
+/// Entries run by a standalone assembler are parsed with its own options, see
+/// [`Dialect`].
+///
+/// Arguments forwarded to another stage with `-Wp,`, `-Wa,`, `-Wl,`,
+/// `-Xpreprocessor`, `-Xclang`, `-Xassembler` or `-Xlinker` are kept as
+/// `Flag::Forwarded`, and are also collected per stage and parsed into
+/// [`ParsedCommand::forwarded`]. The accessors see the flags forwarded to the
+/// preprocessor and frontend after the driver's own flags, so
+/// `-Xclang -include -Xclang pch.h` is reported by
+/// [`ParsedCommand::force_includes`].
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct ParsedCommand {
+    /// `arguments[0]`, the compiler executable
+    pub program: Option<String>,
+    /// The syntax the arguments were parsed with
+    pub dialect: Dialect,
+    pub args: Vec<ParsedArg>,
+    /// The forwarded arguments, parsed per stage in the order they were given
+    pub forwarded: Vec<ForwardedArg>,
+}
+
+/// An argument forwarded to another stage, parsed together with the other
+/// arguments forwarded to that stage
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct ForwardedArg {
+    pub stage: Stage,
+    /// The parsed argument, where `raw` holds the spelling seen by the stage,
+    /// e.g. `["-include", "pch.h"]` for `-Xclang -include -Xclang pch.h`
+    pub arg: ParsedArg,
+}
+
+impl ParsedCommand {
+    /// Parses an argument vector, where `args[0]` names the compiler, in the
+    /// dialect detected with [`Dialect::detect`]
+    #[must_use]
+    pub fn parse<S: AsRef<str>>(args: &[S]) -> Self {
+        Self::parse_with(args, Dialect::detect(args))
+    }
+
+    /// Parses an argument vector, where `args[0]` names the compiler, in the
+    /// given dialect
+    #[must_use]
+    pub fn parse_with<S: AsRef<str>>(args: &[S], dialect: Dialect) -> Self {
+        let program = args.first().map(|program| program.as_ref().to_string());
+        let mut parsed = Vec::new();
+        let mut iter = args.iter().skip(1).map(AsRef::as_ref);
+
+        while let Some(arg) = iter.next() {
+            parsed.push(match dialect {
+                Dialect::Gcc => parse_arg(arg, &mut iter),
+                Dialect::Msvc => parse_msvc_arg(arg, &mut iter),
+                Dialect::Gas => parse_asm_arg(arg, &mut iter, GAS_FLAGS),
+                Dialect::Nasm => parse_asm_arg(arg, &mut iter, NASM_FLAGS),
+                Dialect::Yasm => parse_asm_arg(arg, &mut iter, YASM_FLAGS),
+            });
+        }
+
+        let mut forwarded = Vec::new();
+        for stage in [
+            Stage::Preprocessor,
+            Stage::Frontend,
+            Stage::Assembler,
+            Stage::Linker,
+        ] {
+            let stage_args: Vec<&str> = stage_args(&parsed, stage).collect();
+            let mut iter = stage_args.into_iter();
+            while let Some(arg) = iter.next() {
+                let mut arg = match stage {
+                    Stage::Assembler => parse_asm_arg(arg, &mut iter, GAS_FLAGS),
+                    _ => parse_arg(arg, &mut iter),
+                };
+                // Positional arguments are values of the stage's own flags,
+                // not inputs to the driver
+                if matches!(arg.flag, Flag::Input(_)) {
+                    arg.flag = Flag::Unknown;
+                }
+                forwarded.push(ForwardedArg { stage, arg });
+            }
+        }
+
+        Self {
+            program,
+            dialect,
+            args: parsed,
+            forwarded,
+        }
+    }
+
+    /// Returns the arguments forwarded to `stage`, in the order they were given
+    pub fn stage_args(&self, stage: Stage) -> impl Iterator<Item = &str> {
+        stage_args(&self.args, stage)
+    }
+
+    /// Returns the parsed flags forwarded to `stage`, in the order they were
+    /// given
+    pub fn stage_flags(&self, stage: Stage) -> impl Iterator<Item = &Flag> {
+        self.forwarded
+            .iter()
+            .filter(move |forwarded| forwarded.stage == stage)
+            .map(|forwarded| &forwarded.arg.flag)
+    }
+
+    /// Reassembles the argument vector, with every argument spelled as it
+    /// originally was
+    #[must_use]
+    pub fn to_args(&self) -> Vec<String> {
+        self.program
+            .iter()
+            .cloned()
+            .chain(self.args.iter().flat_map(|arg| arg.raw.iter().cloned()))
+            .collect()
+    }
+
+    /// The driver's flags, followed by those forwarded to the preprocessor and
+    /// frontend
+    fn flags(&self) -> impl Iterator<Item = &Flag> {
+        let forwarded = self
+            .forwarded
+            .iter()
+            .filter(|forwarded| matches!(forwarded.stage, Stage::Preprocessor | Stage::Frontend))
+            .map(|forwarded| &forwarded.arg);
+        self.args.iter().chain(forwarded).map(|arg| &arg.flag)
+    }
+
+    /// Returns the include directories, in the order they were given
+    pub fn include_dirs(&self) -> impl Iterator<Item = (IncludeKind, &Path)> {
+        self.flags().filter_map(|flag| match flag {
+            Flag::Include { kind, path } => Some((*kind, path.as_path())),
+            _ => None,
+        })
+    }
+
+    /// Returns the files included with `-include`, in the order they were given
+    pub fn force_includes(&self) -> impl Iterator<Item = &Path> {
+        self.flags().filter_map(|flag| match flag {
+            Flag::ForceInclude(path) => Some(path.as_path()),
+            _ => None,
+        })
+    }
+
+    /// Returns the macro definitions as `(name, value)` pairs, in the order they
+    /// were given
+    pub fn defines(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.flags().filter_map(|flag| match flag {
+            Flag::Define { name, value } => Some((name.as_str(), value.as_deref())),
+            _ => None,
+        })
+    }
+
+    /// Returns the names of the undefined macros, in the order they were given
+    pub fn undefines(&self) -> impl Iterator<Item = &str> {
+        self.flags().filter_map(|flag| match flag {
+            Flag::Undefine(name) => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Returns the last language standard given, e.g. `c++17`
+    #[must_use]
+    pub fn standard(&self) -> Option<&str> {
+        self.flags()
+            .filter_map(|flag| match flag {
+                Flag::Standard(std) => Some(std.as_str()),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Returns the last language given with `-x`, e.g. `c++`
+    #[must_use]
+    pub fn language(&self) -> Option<&str> {
+        self.flags()
+            .filter_map(|flag| match flag {
+                Flag::Language(lang) => Some(lang.as_str()),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Returns the last target triple given
+    #[must_use]
+    pub fn target(&self) -> Option<&str> {
+        self.flags()
+            .filter_map(|flag| match flag {
+                Flag::Target(target) => Some(target.as_str()),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Returns the last architecture given with `-march=`
+    #[must_use]
+    pub fn arch(&self) -> Option<&str> {
+        self.flags()
+            .filter_map(|flag| match flag {
+                Flag::Arch(arch) => Some(arch.as_str()),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Returns the last architecture given with `-arch`
+    #[must_use]
+    pub fn apple_arch(&self) -> Option<&str> {
+        self.flags()
+            .filter_map(|flag| match flag {
+                Flag::AppleArch(arch) => Some(arch.as_str()),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Returns the last CPU given with `-mcpu=`
+    #[must_use]
+    pub fn cpu(&self) -> Option<&str> {
+        self.flags()
+            .filter_map(|flag| match flag {
+                Flag::Cpu(cpu) => Some(cpu.as_str()),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Returns the last ABI given with `-mabi=`
+    #[must_use]
+    pub fn abi(&self) -> Option<&str> {
+        self.flags()
+            .filter_map(|flag| match flag {
+                Flag::Abi(abi) => Some(abi.as_str()),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Returns the last address model given with `-m32` or `-m64`
+    #[must_use]
+    pub fn address_model(&self) -> Option<AddressModel> {
+        self.flags()
+            .filter_map(|flag| match flag {
+                Flag::AddressModel(model) => Some(*model),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Returns the last sysroot given
+    #[must_use]
+    pub fn sysroot(&self) -> Option<&Path> {
+        self.flags()
+            .filter_map(|flag| match flag {
+                Flag::Sysroot(path) => Some(path.as_path()),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Returns the last output given with `-o`
+    #[must_use]
+    pub fn output(&self) -> Option<&Path> {
+        self.flags()
+            .filter_map(|flag| match flag {
+                Flag::Output(path) => Some(path.as_path()),
+                _ => None,
+            })
+            .last()
+    }
+
+    /// Returns the positional arguments, usually the source files
+    pub fn inputs(&self) -> impl Iterator<Item = &str> {
+        self.flags().filter_map(|flag| match flag {
+            Flag::Input(input) | Flag::TypedInput { path: input, .. } => Some(input.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// Returns the arguments of `args` forwarded to `stage`
+fn stage_args(args: &[ParsedArg], stage: Stage) -> impl Iterator<Item = &str> {
+    args.iter()
+        .filter_map(move |arg| match &arg.flag {
+            Flag::Forwarded { via, args } if via.stage() == stage => Some(args),
+            _ => None,
+        })
+        .flatten()
+        .map(String::as_str)
+}
+
+/// Parses `arg`, consuming its value from `rest` if it is given separately
+fn parse_arg<'a>(arg: &'a str, rest: &mut impl Iterator<Item = &'a str>) -> ParsedArg {
+    let unknown = || ParsedArg {
+        flag: Flag::Unknown,
+        raw: vec![arg.to_string()],
+    };
+
+    for &via in FORWARDERS {
+        let Some(joined) = arg.strip_prefix(via.name()) else {
+            continue;
+        };
+        if via.name().ends_with(',') {
+            return ParsedArg {
+                flag: Flag::Forwarded {
+                    via,
+                    args: joined.split(',').map(ToString::to_string).collect(),
+                },
+                raw: vec![arg.to_string()],
+            };
+        }
+        if joined.is_empty() {
+            return match rest.next() {
+                Some(value) => ParsedArg {
+                    flag: Flag::Forwarded {
+                        via,
+                        args: vec![value.to_string()],
+                    },
+                    raw: vec![arg.to_string(), value.to_string()],
+                },
+                None => unknown(),
+            };
+        }
+    }
+
+    match arg {
+        "-m32" => {
+            return ParsedArg {
+                flag: Flag::AddressModel(AddressModel::Bits32),
+                raw: vec![arg.to_string()],
+            }
+        }
+        "-m64" => {
+            return ParsedArg {
+                flag: Flag::AddressModel(AddressModel::Bits64),
+                raw: vec![arg.to_string()],
+            }
+        }
+        _ if !arg.starts_with('-') || arg == "-" => {
+            return ParsedArg {
+                flag: Flag::Input(arg.to_string()),
+                raw: vec![arg.to_string()],
+            }
+        }
+        _ => {}
+    }
+
+    match_specs(arg, arg, GCC_FLAGS, rest).unwrap_or_else(unknown)
+}
+
+/// Parses an assembler's `arg` against `specs`, consuming its value from
+/// `rest` if it is given separately
+fn parse_asm_arg<'a>(
+    arg: &'a str,
+    rest: &mut impl Iterator<Item = &'a str>,
+    specs: &[FlagSpec],
+) -> ParsedArg {
+    let flag = match arg {
+        "--32" => Flag::AddressModel(AddressModel::Bits32),
+        "--64" => Flag::AddressModel(AddressModel::Bits64),
+        _ if !arg.starts_with('-') || arg == "-" => Flag::Input(arg.to_string()),
+        _ => {
+            return match_specs(arg, arg, specs, rest).unwrap_or_else(|| ParsedArg {
+                flag: Flag::Unknown,
+                raw: vec![arg.to_string()],
+            })
+        }
+    };
+
+    ParsedArg {
+        flag,
+        raw: vec![arg.to_string()],
+    }
+}
+
+/// Parses an MSVC style `arg`, consuming its value from `rest` if it is given
+/// separately. Options are spelled with either a `/` or `-` prefix, and are
+/// case sensitive. Arguments that aren't MSVC options but start with `-` are
+/// parsed as GCC style flags, which `clang-cl` also accepts.
+fn parse_msvc_arg<'a>(arg: &'a str, rest: &mut impl Iterator<Item = &'a str>) -> ParsedArg {
+    let name = arg.strip_prefix(['/', '-']).filter(|name| !name.is_empty());
+    let forced = match name {
+        Some("TC") => Some(Language::C),
+        Some("TP") => Some(Language::Cxx),
+        _ => None,
+    };
+    if let Some(language) = forced {
+        return ParsedArg {
+            flag: Flag::ForceLanguage(language),
+            raw: vec![arg.to_string()],
+        };
+    }
+    if let Some(parsed) = name.and_then(|name| match_specs(arg, name, MSVC_FLAGS, rest)) {
+        return parsed;
+    }
+
+    match arg.strip_prefix('/') {
+        // `cl.exe` reads unknown options as file names, so take anything that
+        // looks like an absolute Unix path as an input
+        Some(path) if path.contains('/') => ParsedArg {
+            flag: Flag::Input(arg.to_string()),
+            raw: vec![arg.to_string()],
+        },
+        Some(_) => ParsedArg {
+            flag: Flag::Unknown,
+            raw: vec![arg.to_string()],
+        },
+        None => parse_arg(arg, rest),
+    }
+}
+
+/// Matches `name`, the option name of `arg` without its prefix, against
+/// `specs`, consuming the option's value from `rest` if it is given separately
+fn match_specs<'a>(
+    arg: &'a str,
+    name: &'a str,
+    specs: &[FlagSpec],
+    rest: &mut impl Iterator<Item = &'a str>,
+) -> Option<ParsedArg> {
+    let (spec, joined) = find_spec(name, specs)?;
+    Some(match (joined, rest) {
+        (Some(value), _) => ParsedArg {
+            flag: (spec.make)(value.to_string()),
+            raw: vec![arg.to_string()],
+        },
+        (None, rest) => match rest.next() {
+            Some(value) => ParsedArg {
+                flag: (spec.make)(value.to_string()),
+                raw: vec![arg.to_string(), value.to_string()],
+            },
+            None => ParsedArg {
+                flag: Flag::Unknown,
+                raw: vec![arg.to_string()],
+            },
+        },
+    })
+}
+
+/// Finds the spec of the longest flag name `name` starts with, along with its
+/// joined value, or `None` if the value is given as the next argument
+fn find_spec<'a, 's>(
+    name: &'a str,
+    specs: &'s [FlagSpec],
+) -> Option<(&'s FlagSpec, Option<&'a str>)> {
+    let matches = specs.iter().filter_map(|spec| {
+        let joined = name.strip_prefix(spec.name)?;
+        let separate = joined.is_empty()
+            && matches!(
+                spec.arity,
+                Arity::JoinedOrSeparate | Arity::Separate | Arity::EqualsOrSeparate
+            );
+        if separate {
+            return Some((spec, None));
+        }
+
+        let value = match spec.arity {
+            Arity::JoinedOrSeparate | Arity::Joined => Some(joined),
+            Arity::EqualsOrSeparate => joined.strip_prefix('='),
+            Arity::Separate => None,
+        };
+        value.map(|value| (spec, Some(value)))
+    });
+    matches.max_by_key(|(spec, _)| spec.name.len())
+}
+
+/// The value of a flag from one of the flag tables, as spelled in a
+/// [`ParsedArg`]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub(crate) struct FlagValue<'a> {
+    pub(crate) kind: ValueKind,
+    /// The flag's spelling before its value, e.g. `-I`, `--sysroot=` or `/Fo:`
+    pub(crate) prefix: &'a str,
+    pub(crate) value: &'a str,
+    /// Whether the value is given as the next argument
+    pub(crate) separate: bool,
+}
+
+impl ParsedArg {
+    /// Looks up the value of the table flag this argument was parsed from, if
+    /// any, using the flag tables of `dialect`
+    pub(crate) fn value(&self, dialect: Dialect) -> Option<FlagValue<'_>> {
+        if matches!(self.flag, Flag::Input(_) | Flag::Forwarded { .. }) {
+            return None;
+        }
+
+        let arg = self.raw.first()?;
+        let (spec, joined) = match dialect {
+            Dialect::Gcc => find_spec(arg, GCC_FLAGS),
+            Dialect::Gas => find_spec(arg, GAS_FLAGS),
+            Dialect::Nasm => find_spec(arg, NASM_FLAGS),
+            Dialect::Yasm => find_spec(arg, YASM_FLAGS),
+            Dialect::Msvc => arg
+                .strip_prefix(['/', '-'])
+                .filter(|name| !name.is_empty())
+                .and_then(|name| find_spec(name, MSVC_FLAGS))
+                .or_else(|| {
+                    // `clang-cl` also accepts GCC style flags
+                    arg.starts_with('-')
+                        .then(|| find_spec(arg, GCC_FLAGS))
+                        .flatten()
+                }),
+        }?;
+
+        match (joined, &self.raw[1..]) {
+            (Some(value), []) => Some(FlagValue {
+                kind: spec.value,
+                prefix: &arg[..arg.len() - value.len()],
+                value,
+                separate: false,
+            }),
+            (None, [value]) => Some(FlagValue {
+                kind: spec.value,
+                prefix: arg,
+                value,
+                separate: true,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl CompileCommand {
+    /// Parses the entry's argument vector into recognized flags, after
+    /// stripping any launchers such as `ccache`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument vector cannot be determined, see
+    /// [`CompileCommand::args`]
+    pub fn parse(&self) -> Result<ParsedCommand, ArgsError> {
+        Ok(ParsedCommand::parse(&self.launched_args()?.compiler_args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> ParsedCommand {
+        ParsedCommand::parse(args)
+    }
+
+    #[test]
+    fn it_parses_joined_and_separate_flags() {
+        let cases: &[(&[&str], Flag)] = &[
+            (&["-Ifoo"], include(IncludeKind::Angled, "foo".into())),
+            (&["-I", "foo"], include(IncludeKind::Angled, "foo".into())),
+            (
+                &["-isystem/usr/inc"],
+                include(IncludeKind::System, "/usr/inc".into()),
+            ),
+            (
+                &["-isystem", "/usr/inc"],
+                include(IncludeKind::System, "/usr/inc".into()),
+            ),
+            (&["-iquote", "q"], include(IncludeKind::Quote, "q".into())),
+            (
+                &["-idirafter", "a"],
+                include(IncludeKind::After, "a".into()),
+            ),
+            (&["-include", "pch.h"], Flag::ForceInclude("pch.h".into())),
+            (&["-DX"], define("X".into())),
+            (&["-DX=1"], define("X=1".into())),
+            (&["-D", "X=1"], define("X=1".into())),
+            (&["-DX="], define("X=".into())),
+            (&["-DX=a=b"], define("X=a=b".into())),
+            (&["-UX"], Flag::Undefine("X".into())),
+            (&["-U", "X"], Flag::Undefine("X".into())),
+            (&["-std=c++17"], Flag::Standard("c++17".into())),
+            (&["--std=c11"], Flag::Standard("c11".into())),
+            (&["-xc++"], Flag::Language("c++".into())),
+            (&["-x", "c"], Flag::Language("c".into())),
+            (
+                &["--target=aarch64-linux-gnu"],
+                Flag::Target("aarch64-linux-gnu".into()),
+            ),
+            (
+                &["--target", "aarch64-linux-gnu"],
+                Flag::Target("aarch64-linux-gnu".into()),
+            ),
+            (
+                &["-target", "x86_64-apple-darwin"],
+                Flag::Target("x86_64-apple-darwin".into()),
+            ),
+            (&["-m32"], Flag::AddressModel(AddressModel::Bits32)),
+            (&["-m64"], Flag::AddressModel(AddressModel::Bits64)),
+            (&["--sysroot=/sr"], Flag::Sysroot("/sr".into())),
+            (&["--sysroot", "/sr"], Flag::Sysroot("/sr".into())),
+            (&["-o", "main.o"], Flag::Output("main.o".into())),
+            (&["-omain.o"], Flag::Output("main.o".into())),
+            (&["-objcmt-migrate-literals"], Flag::Unknown),
+            (&["-object"], Flag::Unknown),
+            (&["main.c"], Flag::Input("main.c".into())),
+            (&["-"], Flag::Input("-".into())),
+            (&["-O2"], Flag::Unknown),
+            (&["-Wall"], Flag::Unknown),
+            (&["-std"], Flag::Unknown),
+            (&["-mavx2"], Flag::Unknown),
+            (&["-target=foo"], Flag::Unknown),
+            (&["--targetfoo"], Flag::Unknown),
+            (&["-I"], Flag::Unknown),
+            (&["-include-pch", "pch.h.pch"], Flag::Unknown),
+        ];
+
+        for (args, expected) in cases {
+            let mut argv = vec!["cc"];
+            argv.extend_from_slice(args);
+            let parsed = parse(&argv);
+
+            assert_eq!(
+                parsed.args,
+                vec![ParsedArg {
+                    flag: expected.clone(),
+                    raw: args.iter().map(ToString::to_string).collect(),
+                }],
+                "parsing {args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_exposes_typed_accessors() {
+        let argv = [
+            "/usr/bin/clang++",
+            "-Iinclude",
+            "-isystem",
+            "/opt/inc",
+            "-DDEBUG",
+            "-DLEVEL=2",
+            "-UNDEBUG",
+            "-std=c++14",
+            "-std=c++17",
+            "-x",
+            "c++",
+            "--target=aarch64-linux-gnu",
+            "-m64",
+            "--sysroot=/sysroot",
+            "-include",
+            "config.h",
+            "-fno-exceptions",
+            "-c",
+            "-o",
+            "main.o",
+            "main.cc",
+        ];
+        let parsed = parse(&argv);
+
+        assert_eq!(parsed.program.as_deref(), Some("/usr/bin/clang++"));
+        assert_eq!(
+            parsed.include_dirs().collect::<Vec<_>>(),
+            vec![
+                (IncludeKind::Angled, Path::new("include")),
+                (IncludeKind::System, Path::new("/opt/inc")),
+            ]
+        );
+        assert_eq!(
+            parsed.defines().collect::<Vec<_>>(),
+            vec![("DEBUG", None), ("LEVEL", Some("2"))]
+        );
+        assert_eq!(parsed.undefines().collect::<Vec<_>>(), vec!["NDEBUG"]);
+        assert_eq!(parsed.standard(), Some("c++17"));
+        assert_eq!(parsed.language(), Some("c++"));
+        assert_eq!(parsed.target(), Some("aarch64-linux-gnu"));
+        assert_eq!(parsed.address_model(), Some(AddressModel::Bits64));
+        assert_eq!(parsed.sysroot(), Some(Path::new("/sysroot")));
+        assert_eq!(
+            parsed.force_includes().collect::<Vec<_>>(),
+            vec![Path::new("config.h")]
+        );
+        assert_eq!(parsed.output(), Some(Path::new("main.o")));
+        assert_eq!(parsed.inputs().collect::<Vec<_>>(), vec!["main.cc"]);
+        assert_eq!(parsed.to_args(), argv);
+    }
+
+    #[test]
+    fn it_consumes_the_values_of_separate_flags() {
+        let cases: &[(&[&str], &[&str])] = &[
+            (
+                &["gcc", "-MD", "-MF", "foo.o.d", "-c", "a.c", "-o", "a.o"],
+                &["a.c"],
+            ),
+            (&["gcc", "-MF", "x.d", "-MT", "x.o", "-c", "a.s"], &["a.s"]),
+            (&["gcc", "-MQ", "$(OBJ)", "-MJ", "a.json", "a.c"], &["a.c"]),
+            (
+                &["clang", "-imacros", "m.h", "-isysroot", "/sdk", "a.c"],
+                &["a.c"],
+            ),
+            (&["gcc", "--param", "max-inline-insns=10", "a.c"], &["a.c"]),
+            (&["clang", "-mllvm", "-inline-threshold=9", "a.c"], &["a.c"]),
+            (
+                &["clang", "-iframework", "Fw", "-F", "Fw2", "a.m"],
+                &["a.m"],
+            ),
+            (&["gcc", "-L", "lib", "-l", "m", "a.c"], &["a.c"]),
+            (
+                &["clang", "-Xclang", "-load", "-Xclang", "p.so", "a.c"],
+                &["a.c"],
+            ),
+            (&["as", "--MD", "a.d", "-I", "inc", "a.s"], &["a.s"]),
+            (
+                &["nasm", "-g", "-F", "dwarf", "-f", "elf64", "a.asm"],
+                &["a.asm"],
+            ),
+            (&["nasm", "-X", "vc", "-Z", "err.txt", "a.asm"], &["a.asm"]),
+            (
+                &["nasm", "--prefix", "_", "-l", "a.lst", "a.asm"],
+                &["a.asm"],
+            ),
+            (
+                &["yasm", "-g", "dwarf2", "-p", "gas", "-r", "cpp", "a.s"],
+                &["a.s"],
+            ),
+            (
+                &["yasm", "-L", "nasm", "--parser=nasm", "a.asm"],
+                &["a.asm"],
+            ),
+        ];
+
+        for (args, inputs) in cases {
+            let parsed = parse(args);
+            assert_eq!(
+                parsed.inputs().collect::<Vec<_>>(),
+                *inputs,
+                "parsing {args:?}"
+            );
+            assert_eq!(parsed.to_args(), *args);
+        }
+    }
+
+    #[test]
+    fn it_parses_assembler_pre_includes() {
+        let cases: &[(&[&str], &[&str])] = &[
+            (
+                &["nasm", "-P", "pre.inc", "-pmore.inc", "a.asm"],
+                &["pre.inc", "more.inc"],
+            ),
+            (&["yasm", "-P", "pre.inc", "-p", "gas", "a.s"], &["pre.inc"]),
+        ];
+
+        for (args, includes) in cases {
+            let parsed = parse(args);
+            assert_eq!(
+                parsed.force_includes().collect::<Vec<_>>(),
+                includes.iter().map(Path::new).collect::<Vec<_>>(),
+                "parsing {args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_looks_up_flag_values() {
+        type Value<'a> = (ValueKind, &'a str, &'a str, bool);
+
+        let cases: &[(Dialect, &[&str], Option<Value>)] = &[
+            (
+                Dialect::Gcc,
+                &["-Ifoo"],
+                Some((ValueKind::Path, "-I", "foo", false)),
+            ),
+            (
+                Dialect::Gcc,
+                &["-I", "foo"],
+                Some((ValueKind::Path, "-I", "foo", true)),
+            ),
+            (
+                Dialect::Gcc,
+                &["-isysroot/sdk"],
+                Some((ValueKind::Path, "-isysroot", "/sdk", false)),
+            ),
+            (
+                Dialect::Gcc,
+                &["--sysroot=/sr"],
+                Some((ValueKind::Path, "--sysroot=", "/sr", false)),
+            ),
+            (
+                Dialect::Gcc,
+                &["-MFa.d"],
+                Some((ValueKind::Output, "-MF", "a.d", false)),
+            ),
+            (
+                Dialect::Gcc,
+                &["-MT", "a.o"],
+                Some((ValueKind::DependencyTarget, "-MT", "a.o", true)),
+            ),
+            (
+                Dialect::Gcc,
+                &["-DX=1"],
+                Some((ValueKind::Other, "-D", "X=1", false)),
+            ),
+            (Dialect::Gcc, &["-c"], None),
+            (Dialect::Gcc, &["a.c"], None),
+            (
+                Dialect::Msvc,
+                &["-Fomain.obj"],
+                Some((ValueKind::Output, "-Fo", "main.obj", false)),
+            ),
+            (
+                Dialect::Msvc,
+                &["/Fe:", "a.exe"],
+                Some((ValueKind::Output, "/Fe:", "a.exe", true)),
+            ),
+            (
+                Dialect::Msvc,
+                &["/Iinc"],
+                Some((ValueKind::Path, "/I", "inc", false)),
+            ),
+            (
+                Dialect::Msvc,
+                &["--sysroot=/sr"],
+                Some((ValueKind::Path, "--sysroot=", "/sr", false)),
+            ),
+            (Dialect::Msvc, &["/c"], None),
+        ];
+
+        for (dialect, raw, expected) in cases {
+            let arg = ParsedArg {
+                flag: Flag::Unknown,
+                raw: raw.iter().map(ToString::to_string).collect(),
+            };
+            let value = arg
+                .value(*dialect)
+                .map(|value| (value.kind, value.prefix, value.value, value.separate));
+            assert_eq!(value, *expected, "looking up {raw:?}");
+        }
+    }
+
+    #[test]
+    fn it_unpacks_forwarded_arguments() {
+        let argv = [
+            "gcc",
+            "-Wp,-DFOO,-Ibar",
+            "-Xclang",
+            "-include",
+            "-Xclang",
+            "pch.h",
+            "-Xclang",
+            "-load",
+            "-Xclang",
+            "plugin.so",
+            "-Wa,--defsym,X=1",
+            "-Xassembler",
+            "-I",
+            "-Xassembler",
+            "asm",
+            "-Wl,-rpath,/lib",
+            "-Xlinker",
+            "--as-needed",
+            "-Xpreprocessor",
+            "-DBAZ=1",
+            "-Wall",
+            "-c",
+            "a.c",
+            "-Xclang",
+        ];
+        let parsed = parse(&argv);
+
+        assert_eq!(
+            parsed.args[0].flag,
+            Flag::Forwarded {
+                via: Forwarder::Wp,
+                args: vec!["-DFOO".into(), "-Ibar".into()],
+            }
+        );
+        assert_eq!(parsed.args.last().unwrap().flag, Flag::Unknown);
+        let stages: &[(Stage, &[&str])] = &[
+            (Stage::Preprocessor, &["-DFOO", "-Ibar", "-DBAZ=1"]),
+            (
+                Stage::Frontend,
+                &["-include", "pch.h", "-load", "plugin.so"],
+            ),
+            (Stage::Assembler, &["--defsym", "X=1", "-I", "asm"]),
+            (Stage::Linker, &["-rpath", "/lib", "--as-needed"]),
+        ];
+        for (stage, args) in stages {
+            assert_eq!(parsed.stage_args(*stage).collect::<Vec<_>>(), *args);
+        }
+
+        assert_eq!(
+            parsed.defines().collect::<Vec<_>>(),
+            vec![("FOO", None), ("BAZ", Some("1"))]
+        );
+        assert_eq!(
+            parsed.include_dirs().collect::<Vec<_>>(),
+            vec![(IncludeKind::Angled, Path::new("bar"))]
+        );
+        assert_eq!(
+            parsed.force_includes().collect::<Vec<_>>(),
+            vec![Path::new("pch.h")]
+        );
+        assert_eq!(parsed.inputs().collect::<Vec<_>>(), vec!["a.c"]);
+        assert_eq!(
+            parsed.forwarded[1],
+            ForwardedArg {
+                stage: Stage::Preprocessor,
+                arg: ParsedArg {
+                    flag: include(IncludeKind::Angled, "bar".into()),
+                    raw: vec!["-Ibar".into()],
+                },
+            }
+        );
+        assert_eq!(parsed.to_args(), argv);
+    }
+
+    #[test]
+    fn it_packs_forwarded_arguments() {
+        let cases: &[(Forwarder, &[&str], &[&str])] = &[
+            (Forwarder::Wp, &["-DA", "-DB"], &["-Wp,-DA,-DB"]),
+            (Forwarder::Wa, &["--defsym", "X=1"], &["-Wa,--defsym,X=1"]),
+            (Forwarder::Wl, &["-rpath", "/lib"], &["-Wl,-rpath,/lib"]),
+            (
+                Forwarder::Xclang,
+                &["-include", "pch.h"],
+                &["-Xclang", "-include", "-Xclang", "pch.h"],
+            ),
+            (Forwarder::Xlinker, &["-v"], &["-Xlinker", "-v"]),
+        ];
+
+        for (via, args, packed) in cases {
+            assert_eq!(via.pack(args), *packed);
+            let parsed = parse(&[&["cc"], *packed].concat());
+            assert_eq!(
+                parsed.stage_args(via.stage()).collect::<Vec<_>>(),
+                *args,
+                "round tripping {packed:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_parses_msvc_options() {
+        let cases: &[(&[&str], Flag)] = &[
+            (&["/Ifoo"], include(IncludeKind::Angled, "foo".into())),
+            (&["-Ifoo"], include(IncludeKind::Angled, "foo".into())),
+            (&["/I", "foo"], include(IncludeKind::Angled, "foo".into())),
+            (
+                &[r"/IC:\Program Files\inc"],
+                include(IncludeKind::Angled, r"C:\Program Files\inc".into()),
+            ),
+            (
+                &["/external:Isys"],
+                include(IncludeKind::System, "sys".into()),
+            ),
+            (
+                &["-external:I", "sys"],
+                include(IncludeKind::System, "sys".into()),
+            ),
+            (
+                &["-imsvc", "sdk"],
+                include(IncludeKind::System, "sdk".into()),
+            ),
+            (&["/imsvcsdk"], include(IncludeKind::System, "sdk".into())),
+            (&["/DFOO"], define("FOO".into())),
+            (&["-D", "FOO=1"], define("FOO=1".into())),
+            (&["/DFOO#1"], define("FOO=1".into())),
+            (&["/UNDEBUG"], Flag::Undefine("NDEBUG".into())),
+            (&["/FIpch.h"], Flag::ForceInclude("pch.h".into())),
+            (&["/FI", "pch.h"], Flag::ForceInclude("pch.h".into())),
+            (&["/std:c++20"], Flag::Standard("c++20".into())),
+            (&["-std:c17"], Flag::Standard("c17".into())),
+            (&["/Foout.obj"], Flag::Output("out.obj".into())),
+            (&["/Fo:out.obj"], Flag::Output("out.obj".into())),
+            (&["/Fo:", "out.obj"], Flag::Output("out.obj".into())),
+            (
+                &["--target=x86_64-pc-windows-msvc"],
+                Flag::Target("x86_64-pc-windows-msvc".into()),
+            ),
+            (&["-m32"], Flag::AddressModel(AddressModel::Bits32)),
+            (&["main.cc"], Flag::Input("main.cc".into())),
+            (
+                &["/home/me/main.cc"],
+                Flag::Input("/home/me/main.cc".into()),
+            ),
+            // Options are case sensitive
+            (&["/i", "foo"], Flag::Unknown),
+            (&["/fo", "out.obj"], Flag::Unknown),
+            (&["/c"], Flag::Unknown),
+            (&["/EHsc"], Flag::Unknown),
+            (&["/"], Flag::Unknown),
+        ];
+
+        for (args, flag) in cases {
+            let argv = [&["cl.exe"], *args].concat();
+            let parsed = ParsedCommand::parse_with(&argv, Dialect::Msvc);
+            assert_eq!(parsed.args[0].flag, *flag, "parsing {args:?}");
+            assert_eq!(parsed.to_args(), argv);
+        }
+    }
+
+    #[test]
+    fn it_detects_the_msvc_dialect() {
+        let argv = [
+            r"C:\LLVM\bin\clang-cl.exe",
+            "/nologo",
+            r"/IC:\inc",
+            "/external:I",
+            r"C:\ext",
+            "-imsvc",
+            r"C:\sdk",
+            "/DWIN32",
+            "/D_DEBUG#1",
+            "/FIpch.h",
+            "/std:c++20",
+            "-Xclang",
+            "-DFROM_CC1",
+            "/c",
+            "/Fo:main.obj",
+            "main.cpp",
+        ];
+        let parsed = parse(&argv);
+
+        assert_eq!(parsed.dialect, Dialect::Msvc);
+        assert_eq!(
+            parsed.include_dirs().collect::<Vec<_>>(),
+            vec![
+                (IncludeKind::Angled, Path::new(r"C:\inc")),
+                (IncludeKind::System, Path::new(r"C:\ext")),
+                (IncludeKind::System, Path::new(r"C:\sdk")),
+            ]
+        );
+        assert_eq!(
+            parsed.defines().collect::<Vec<_>>(),
+            vec![("WIN32", None), ("_DEBUG", Some("1")), ("FROM_CC1", None)]
+        );
+        assert_eq!(
+            parsed.force_includes().collect::<Vec<_>>(),
+            vec![Path::new("pch.h")]
+        );
+        assert_eq!(parsed.standard(), Some("c++20"));
+        assert_eq!(parsed.output(), Some(Path::new("main.obj")));
+        assert_eq!(parsed.inputs().collect::<Vec<_>>(), vec!["main.cpp"]);
+        assert_eq!(parsed.to_args(), argv);
+
+        assert_eq!(
+            parse(&["clang", "--driver-mode=cl", "/Ifoo"]).dialect,
+            Dialect::Msvc
+        );
+        assert_eq!(parse(&["gcc", "/Ifoo"]).dialect, Dialect::Gcc);
+    }
+
+    #[test]
+    fn it_parses_compile_commands() {
+        let comp_data = crate::from_compile_flags_txt(Path::new("/proj"), "-xc++\n-I\ninc");
+        let parsed = comp_data[0].parse().unwrap();
+
+        assert_eq!(parsed.program.as_deref(), Some("clang"));
+        assert_eq!(parsed.language(), Some("c++"));
+        assert_eq!(
+            parsed.include_dirs().collect::<Vec<_>>(),
+            vec![(IncludeKind::Angled, Path::new("inc"))]
+        );
+    }
+}
@@ -0,0 +1,468 @@
+use std::fmt::{self, Display};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::parsed::ValueKind;
+use crate::{ArgsError, CompileCommand, Dialect, ParsedCommand, SourceFile};
+
+/// How relative paths in a `CompileCommand` are made absolute
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq)]
+pub enum PathResolution {
+    /// Join the path onto `directory` and normalize it lexically, without
+    /// touching the filesystem
+    #[default]
+    Lexical,
+    /// Join the path onto `directory` and canonicalize it, following symlinks.
+    /// The path must exist, except for output files, of which only the parent
+    /// directory is canonicalized.
+    Canonical,
+}
+
+/// Errors that can occur while resolving the paths in an entry's arguments
+#[derive(Debug)]
+pub enum ResolveError {
+    /// The entry's argument vector could not be determined
+    Args(ArgsError),
+    /// A path could not be canonicalized
+    Io { path: PathBuf, source: io::Error },
+}
+
+impl Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Args(e) => write!(f, "{e}"),
+            Self::Io { path, source } => {
+                write!(f, "failed to canonicalize {}: {source}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Args(e) => Some(e),
+            Self::Io { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<ArgsError> for ResolveError {
+    fn from(e: ArgsError) -> Self {
+        Self::Args(e)
+    }
+}
+
+/// Lexically normalizes `path`, removing `.` components, duplicate separators,
+/// and `..` components along with the component they cancel out
+///
+/// The filesystem is not consulted, so symlinks are not taken into account.
+/// Leading `..` components of a relative path are kept, and `..` directly
+/// under the root is dropped.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir | Component::Normal(_) => {
+                normalized.push(component);
+            }
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::Prefix(_) | Component::RootDir) => {}
+                Some(Component::ParentDir | Component::CurDir) | None => {
+                    normalized.push(component);
+                }
+            },
+        }
+    }
+
+    normalized
+}
+
+/// Joins `path` onto `directory` unless it is already absolute, then
+/// normalizes the result
+pub(crate) fn resolve(directory: &Path, path: &Path) -> PathBuf {
+    normalize(&directory.join(path))
+}
+
+impl CompileCommand {
+    /// Returns `file` joined onto `directory` and lexically normalized, or `None`
+    /// for a `compile_flags.txt` derived entry
+    #[must_use]
+    pub fn resolved_file(&self) -> Option<PathBuf> {
+        match &self.file {
+            SourceFile::All => None,
+            SourceFile::File(file) => Some(resolve(&self.directory, file)),
+        }
+    }
+
+    /// Returns `output`, if present, joined onto `directory` and lexically
+    /// normalized
+    #[must_use]
+    pub fn resolved_output(&self) -> Option<PathBuf> {
+        self.output
+            .as_ref()
+            .map(|output| resolve(&self.directory, output))
+    }
+
+    /// Makes `path` absolute relative to `directory` according to `mode`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `mode` is `PathResolution::Canonical` and the path
+    /// cannot be canonicalized
+    pub fn resolve_path(&self, path: &Path, mode: PathResolution) -> io::Result<PathBuf> {
+        match mode {
+            PathResolution::Lexical => Ok(resolve(&self.directory, path)),
+            PathResolution::Canonical => self.directory.join(path).canonicalize(),
+        }
+    }
+
+    /// Canonicalizes the parent directory of `path`, an output file that may
+    /// not exist yet
+    fn canonicalize_output(&self, path: &Path) -> io::Result<PathBuf> {
+        let path = self.directory.join(path);
+        match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => Ok(parent.canonicalize()?.join(name)),
+            _ => path.canonicalize(),
+        }
+    }
+
+    /// Returns the entry's argument vector with the values of path-bearing
+    /// flags such as `-I`, `-isystem`, `-include`, `-o` and `--sysroot`, as well
+    /// as the `file` argument, made absolute according to `mode`
+    ///
+    /// Joined and separate flag forms are preserved. Joined values, such as
+    /// `-Iinclude`, are rewritten for GCC style and assembler commands, but not
+    /// for MSVC style commands. Output files
+    /// such as `-o` and `-MF` need not exist in `PathResolution::Canonical`
+    /// mode, as long as their parent directory does.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument vector cannot be determined, or a path
+    /// cannot be canonicalized
+    pub fn resolved_args(&self, mode: PathResolution) -> Result<Vec<String>, ResolveError> {
+        let launched = self.launched_args()?;
+        let parsed = ParsedCommand::parse(&launched.compiler_args);
+        let resolve_arg = |value: &str, kind: ValueKind| -> Result<String, ResolveError> {
+            let path = Path::new(value);
+            let resolved = match (kind, mode) {
+                (ValueKind::Output, PathResolution::Canonical) => self.canonicalize_output(path),
+                _ => self.resolve_path(path, mode),
+            };
+            let resolved = resolved.map_err(|source| ResolveError::Io {
+                path: self.directory.join(path),
+                source,
+            })?;
+            Ok(resolved.to_string_lossy().into_owned())
+        };
+        let file = match &self.file {
+            SourceFile::All => None,
+            SourceFile::File(file) => Some(file.as_path()),
+        };
+
+        let mut resolved = launched.launcher.clone();
+        resolved.extend(parsed.program.iter().cloned());
+        for arg in &parsed.args {
+            // Joined values aren't split off MSVC style options, where the
+            // flag table doesn't cover every option a joined value could be
+            // mistaken for
+            let value = arg
+                .value(parsed.dialect)
+                .filter(|value| value.separate || parsed.dialect != Dialect::Msvc);
+            match value {
+                Some(value) if matches!(value.kind, ValueKind::Path | ValueKind::Output) => {
+                    let path = resolve_arg(value.value, value.kind)?;
+                    if value.separate {
+                        resolved.push(value.prefix.to_string());
+                        resolved.push(path);
+                    } else {
+                        resolved.push(format!("{}{path}", value.prefix));
+                    }
+                }
+                _ => match arg.raw.as_slice() {
+                    [raw] if file == Some(Path::new(raw)) => {
+                        resolved.push(resolve_arg(raw, ValueKind::Path)?)
+                    }
+                    raw => resolved.extend(raw.iter().cloned()),
+                },
+            }
+        }
+
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompileArgs;
+
+    fn comp_cmd(directory: &str, args: &[&str]) -> CompileCommand {
+        CompileCommand {
+            directory: PathBuf::from(directory),
+            file: SourceFile::File(PathBuf::from("../src/main.c")),
+            arguments: Some(CompileArgs::Arguments(
+                args.iter().map(ToString::to_string).collect(),
+            )),
+            command: None,
+            output: Some(PathBuf::from("./obj//main.o")),
+        }
+    }
+
+    #[test]
+    fn it_normalizes_paths() {
+        let cases: &[(&str, &str)] = &[
+            ("", ""),
+            (".", ""),
+            ("a/b", "a/b"),
+            ("a//b", "a/b"),
+            ("a/./b/.", "a/b"),
+            ("a/b/../c", "a/c"),
+            ("a/../../b", "../b"),
+            ("../a", "../a"),
+            ("./../a", "../a"),
+            ("/a/b/../../..", "/"),
+            ("/../a", "/a"),
+            ("/a/./b//c/../d/", "/a/b/d"),
+        ];
+
+        for (path, expected) in cases {
+            assert_eq!(
+                normalize(Path::new(path)),
+                PathBuf::from(expected),
+                "normalizing {path:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_resolves_paths() {
+        assert_eq!(
+            resolve(Path::new("/build"), Path::new("../src/main.c")),
+            PathBuf::from("/src/main.c")
+        );
+        assert_eq!(
+            resolve(Path::new("/build"), Path::new("/abs/./main.c")),
+            PathBuf::from("/abs/main.c")
+        );
+    }
+
+    #[test]
+    fn it_resolves_file_and_output() {
+        let proj = Path::new("/proj");
+        let comp_cmd = comp_cmd(&proj.join("build").to_string_lossy(), &["cc"]);
+
+        assert_eq!(
+            comp_cmd.resolved_file(),
+            Some(proj.join("src").join("main.c"))
+        );
+        assert_eq!(
+            comp_cmd.resolved_output(),
+            Some(proj.join("build").join("obj").join("main.o"))
+        );
+        assert_eq!(
+            crate::from_compile_flags_txt(Path::new("/proj"), "-Iinc")[0].resolved_file(),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn it_resolves_path_flags() {
+        let comp_cmd = comp_cmd(
+            "/proj/build",
+            &[
+                "cc",
+                "-I../include",
+                "-I",
+                "gen/./inc",
+                "-isystem",
+                "/usr/include",
+                "-iquote..",
+                "-include",
+                "../config.h",
+                "-include-pch",
+                "pch.h.pch",
+                "--sysroot=../sysroot",
+                "--sysroot",
+                "sr",
+                "-DPATH=../not/a/path",
+                "-O2",
+                "-o",
+                "obj/main.o",
+                "-c",
+                "../src/main.c",
+            ],
+        );
+
+        assert_eq!(
+            comp_cmd.resolved_args(PathResolution::Lexical).unwrap(),
+            vec![
+                "cc",
+                "-I/proj/include",
+                "-I",
+                "/proj/build/gen/inc",
+                "-isystem",
+                "/usr/include",
+                "-iquote/proj",
+                "-include",
+                "/proj/config.h",
+                "-include-pch",
+                "/proj/build/pch.h.pch",
+                "--sysroot=/proj/sysroot",
+                "--sysroot",
+                "/proj/build/sr",
+                "-DPATH=../not/a/path",
+                "-O2",
+                "-o",
+                "/proj/build/obj/main.o",
+                "-c",
+                "/proj/src/main.c",
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn it_leaves_joined_msvc_values_alone() {
+        let comp_cmd = comp_cmd(
+            "/proj/build",
+            &[
+                "clang-cl",
+                "-Fomain.obj",
+                "/Iinc",
+                "/I",
+                "gen",
+                "-I",
+                "inc2",
+                "/c",
+                "../src/main.c",
+            ],
+        );
+
+        assert_eq!(
+            comp_cmd.resolved_args(PathResolution::Lexical).unwrap(),
+            vec![
+                "clang-cl",
+                "-Fomain.obj",
+                "/Iinc",
+                "/I",
+                "/proj/build/gen",
+                "-I",
+                "/proj/build/inc2",
+                "/c",
+                "/proj/src/main.c",
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn it_resolves_assembler_include_dirs() {
+        let cases: &[(&[&str], &[&str])] = &[
+            (
+                &[
+                    "nasm",
+                    "-I",
+                    "inc/",
+                    "-i../asm/",
+                    "-P",
+                    "pre.inc",
+                    "-f",
+                    "elf64",
+                ],
+                &[
+                    "nasm",
+                    "-I",
+                    "/proj/build/inc",
+                    "-i/proj/asm",
+                    "-P",
+                    "/proj/build/pre.inc",
+                    "-f",
+                    "elf64",
+                ],
+            ),
+            (
+                &["yasm", "-I", "inc", "-p", "gas"],
+                &["yasm", "-I", "/proj/build/inc", "-p", "gas"],
+            ),
+            (
+                &["as", "-I", "inc", "-o", "a.o"],
+                &["as", "-I", "/proj/build/inc", "-o", "/proj/build/a.o"],
+            ),
+        ];
+
+        for (args, expected) in cases {
+            assert_eq!(
+                comp_cmd("/proj/build", args)
+                    .resolved_args(PathResolution::Lexical)
+                    .unwrap(),
+                *expected,
+                "resolving {args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_leaves_incomplete_flags_alone() {
+        let comp_cmd = comp_cmd("/proj", &["../cc", "-I"]);
+
+        assert_eq!(
+            comp_cmd.resolved_args(PathResolution::Lexical).unwrap(),
+            vec!["../cc", "-I"]
+        );
+    }
+
+    #[test]
+    fn it_leaves_flags_spelled_like_outputs_alone() {
+        let comp_cmd = comp_cmd("/proj", &["clang", "-objcmt-migrate-literals", "-object"]);
+
+        assert_eq!(
+            comp_cmd.resolved_args(PathResolution::Lexical).unwrap(),
+            vec!["clang", "-objcmt-migrate-literals", "-object"]
+        );
+    }
+
+    #[test]
+    fn it_canonicalizes_paths() {
+        let dir = std::env::temp_dir().canonicalize().unwrap();
+        let comp_cmd = comp_cmd(dir.to_str().unwrap(), &["cc", "-I.", "-Idoes-not-exist"]);
+
+        assert_eq!(
+            comp_cmd
+                .resolve_path(Path::new("."), PathResolution::Canonical)
+                .unwrap(),
+            dir
+        );
+        assert!(matches!(
+            comp_cmd.resolved_args(PathResolution::Canonical),
+            Err(ResolveError::Io { path, .. }) if path == dir.join("does-not-exist")
+        ));
+    }
+
+    #[test]
+    fn it_canonicalizes_the_directory_of_outputs() {
+        let dir = std::env::temp_dir().canonicalize().unwrap();
+        let comp_cmd = comp_cmd(
+            dir.to_str().unwrap(),
+            &["cc", "-I.", "-MFnot-yet.d", "-o", "./not-yet.o"],
+        );
+
+        assert_eq!(
+            comp_cmd.resolved_args(PathResolution::Canonical).unwrap(),
+            vec![
+                String::from("cc"),
+                format!("-I{}", dir.to_string_lossy()),
+                format!("-MF{}", dir.join("not-yet.d").to_string_lossy()),
+                String::from("-o"),
+                dir.join("not-yet.o").to_string_lossy().into_owned(),
+            ]
+        );
+    }
+}
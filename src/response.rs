@@ -0,0 +1,408 @@
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::path::resolve;
+use crate::tokenize::tokenize_windows;
+use crate::{ArgsError, CompileCommand, DriverInfo, LaunchedArgs};
+
+/// The quoting rules used to split the contents of a response file
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum ResponseFileSyntax {
+    /// The rules of GCC and clang: whitespace separates arguments, single and
+    /// double quotes group them, and a backslash escapes the next character
+    Gnu,
+    /// The rules of `cl.exe` and `clang-cl`: each line is split following
+    /// [`TokenizeMode::Windows`](crate::TokenizeMode::Windows)
+    Windows,
+}
+
+impl ResponseFileSyntax {
+    /// Picks the syntax understood by the compiler of an argument vector
+    #[must_use]
+    pub fn detect<S: AsRef<str>>(args: &[S]) -> Self {
+        let args = args.iter().map(|arg| arg.as_ref().to_string()).collect();
+        let compiler_args = LaunchedArgs::from_args(args).compiler_args;
+        if DriverInfo::from_args(&compiler_args).driver.is_msvc_like() {
+            Self::Windows
+        } else {
+            Self::Gnu
+        }
+    }
+
+    /// Splits the contents of a response file into arguments
+    #[must_use]
+    pub fn tokenize(self, contents: &str) -> Vec<String> {
+        let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+        match self {
+            Self::Gnu => tokenize_gnu(contents),
+            Self::Windows => contents
+                .lines()
+                .flat_map(|line| tokenize_windows(line, false))
+                .collect(),
+        }
+    }
+}
+
+/// Options controlling how [`expand_response_files`] reads response files
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct ResponseFileOptions {
+    /// The quoting rules of the response files. If `None`, they are detected
+    /// with [`ResponseFileSyntax::detect`].
+    pub syntax: Option<ResponseFileSyntax>,
+    /// How many response files may be nested inside each other. Defaults to
+    /// 16.
+    pub max_depth: usize,
+}
+
+impl Default for ResponseFileOptions {
+    fn default() -> Self {
+        Self {
+            syntax: None,
+            max_depth: 16,
+        }
+    }
+}
+
+/// Errors that can occur while expanding response files
+#[derive(Debug)]
+pub enum ResponseFileError {
+    /// The entry's argument vector could not be determined
+    Args(ArgsError),
+    /// A response file could not be read
+    Io { path: PathBuf, source: io::Error },
+    /// A response file includes itself, directly or through other response
+    /// files
+    Cycle { path: PathBuf },
+    /// Response files are nested more deeply than
+    /// [`ResponseFileOptions::max_depth`]
+    TooDeep { path: PathBuf, max_depth: usize },
+}
+
+impl Display for ResponseFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Args(e) => write!(f, "{e}"),
+            Self::Io { path, source } => {
+                write!(
+                    f,
+                    "failed to read response file {}: {source}",
+                    path.display()
+                )
+            }
+            Self::Cycle { path } => {
+                write!(f, "response file {} includes itself", path.display())
+            }
+            Self::TooDeep { path, max_depth } => write!(
+                f,
+                "response file {} is nested more than {max_depth} levels deep",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ResponseFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Args(e) => Some(e),
+            Self::Io { source, .. } => Some(source),
+            Self::Cycle { .. } | Self::TooDeep { .. } => None,
+        }
+    }
+}
+
+impl From<ArgsError> for ResponseFileError {
+    fn from(e: ArgsError) -> Self {
+        Self::Args(e)
+    }
+}
+
+/// Splits `contents` following libiberty's `buildargv`, which GCC and clang use
+/// for response files. Unterminated quotes and trailing backslashes are
+/// accepted rather than reported.
+fn tokenize_gnu(contents: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+    let mut chars = contents.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', _) => {
+                current.push(chars.next().unwrap_or('\\'));
+                in_arg = true;
+            }
+            (c, Some(q)) if c == q => quote = None,
+            (c, Some(_)) => current.push(c),
+            ('\'' | '"', None) => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (c, None) if c.is_ascii_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (c, None) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+/// Replaces every `@file` argument after `args[0]` with the arguments read from
+/// `file`, recursively. Relative paths, including those of nested response
+/// files, are resolved against `directory`.
+///
+/// # Errors
+///
+/// Returns an error if a response file cannot be read, includes itself, or is
+/// nested more than [`ResponseFileOptions::max_depth`] levels deep
+pub fn expand_response_files<S: AsRef<str>>(
+    args: &[S],
+    directory: &Path,
+    options: &ResponseFileOptions,
+) -> Result<Vec<String>, ResponseFileError> {
+    let syntax = options
+        .syntax
+        .unwrap_or_else(|| ResponseFileSyntax::detect(args));
+    let mut expanded = Vec::with_capacity(args.len());
+    let mut stack = Vec::new();
+
+    let mut args = args.iter().map(AsRef::as_ref);
+    expanded.extend(args.next().map(ToString::to_string));
+    for arg in args {
+        expand_arg(
+            arg,
+            directory,
+            syntax,
+            options.max_depth,
+            &mut stack,
+            &mut expanded,
+        )?;
+    }
+
+    Ok(expanded)
+}
+
+/// Expands a single argument into `expanded`. `stack` holds the response files
+/// currently being expanded.
+fn expand_arg(
+    arg: &str,
+    directory: &Path,
+    syntax: ResponseFileSyntax,
+    max_depth: usize,
+    stack: &mut Vec<PathBuf>,
+    expanded: &mut Vec<String>,
+) -> Result<(), ResponseFileError> {
+    let Some(file) = arg.strip_prefix('@').filter(|file| !file.is_empty()) else {
+        expanded.push(arg.to_string());
+        return Ok(());
+    };
+
+    let path = resolve(directory, Path::new(file));
+    if stack.contains(&path) {
+        return Err(ResponseFileError::Cycle { path });
+    }
+    if stack.len() == max_depth {
+        return Err(ResponseFileError::TooDeep { path, max_depth });
+    }
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(source) => return Err(ResponseFileError::Io { path, source }),
+    };
+
+    stack.push(path);
+    for arg in syntax.tokenize(&contents) {
+        expand_arg(&arg, directory, syntax, max_depth, stack, expanded)?;
+    }
+    stack.pop();
+
+    Ok(())
+}
+
+impl CompileCommand {
+    /// Returns the entry's argument vector with its response files expanded,
+    /// see [`expand_response_files`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument vector cannot be determined, see
+    /// [`CompileCommand::args`], or a response file cannot be expanded
+    pub fn expanded_args(&self) -> Result<Vec<String>, ResponseFileError> {
+        self.expanded_args_with(&ResponseFileOptions::default())
+    }
+
+    /// Like [`CompileCommand::expanded_args`], with explicit options
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument vector cannot be determined, see
+    /// [`CompileCommand::args`], or a response file cannot be expanded
+    pub fn expanded_args_with(
+        &self,
+        options: &ResponseFileOptions,
+    ) -> Result<Vec<String>, ResponseFileError> {
+        expand_response_files(&self.args()?, &self.directory, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use crate::CompilationDatabase;
+
+    #[test]
+    fn it_tokenizes_gnu_response_files() {
+        let cases: &[(&str, &[&str])] = &[
+            ("-Ia -Ib\n-DX=1\r\n", &["-Ia", "-Ib", "-DX=1"]),
+            (
+                "\"-I/with space\" '-DY=\"q\"'",
+                &["-I/with space", "-DY=\"q\""],
+            ),
+            (r"-I/with\ space -DZ=\'", &["-I/with space", "-DZ='"]),
+            (r#""a\"b" 'c\'d'"#, &["a\"b", "c'd"]),
+            (r"C:\\path", &[r"C:\path"]),
+            ("'' \"\"", &["", ""]),
+            ("\u{feff}-DBOM", &["-DBOM"]),
+            ("'unterminated arg", &["unterminated arg"]),
+            ("trailing\\", &["trailing\\"]),
+            ("", &[]),
+        ];
+
+        for (contents, expected) in cases {
+            assert_eq!(
+                ResponseFileSyntax::Gnu.tokenize(contents),
+                *expected,
+                "tokenizing {contents:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_tokenizes_windows_response_files() {
+        let cases: &[(&str, &[&str])] = &[
+            (r"/IC:\inc /DX=1", &[r"/IC:\inc", "/DX=1"]),
+            (
+                r#""/IC:\Program Files\inc" /c"#,
+                &[r"/IC:\Program Files\inc", "/c"],
+            ),
+            (r#"/DY=\"q\""#, &[r#"/DY="q""#]),
+            ("\u{feff}/nologo\r\n/W4", &["/nologo", "/W4"]),
+        ];
+
+        for (contents, expected) in cases {
+            assert_eq!(
+                ResponseFileSyntax::Windows.tokenize(contents),
+                *expected,
+                "tokenizing {contents:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_detects_response_file_syntax() {
+        let cases: &[(&[&str], ResponseFileSyntax)] = &[
+            (&["gcc", "@a.rsp"], ResponseFileSyntax::Gnu),
+            (&["clang++", "@a.rsp"], ResponseFileSyntax::Gnu),
+            (&["cl.exe", "@a.rsp"], ResponseFileSyntax::Windows),
+            (
+                &["sccache", "clang-cl", "@a.rsp"],
+                ResponseFileSyntax::Windows,
+            ),
+            (
+                &["clang", "--driver-mode=cl", "@a.rsp"],
+                ResponseFileSyntax::Windows,
+            ),
+        ];
+
+        for (args, syntax) in cases {
+            assert_eq!(
+                ResponseFileSyntax::detect(args),
+                *syntax,
+                "detecting {args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_expands_nested_response_files() {
+        let tmp = TempDir::new("response-nested");
+        tmp.write("flags.rsp", "-Iinclude @rsp/defines.rsp -Wall");
+        tmp.write("rsp/defines.rsp", "'-DNAME=\"a b\"'\n-DX");
+        let db: CompilationDatabase = format!(
+            r#"[{{"directory": {:?}, "file": "a.c", "arguments": ["gcc", "@flags.rsp", "-c", "a.c", "@"]}}]"#,
+            tmp.path()
+        )
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            db[0].expanded_args().unwrap(),
+            [
+                "gcc",
+                "-Iinclude",
+                "-DNAME=\"a b\"",
+                "-DX",
+                "-Wall",
+                "-c",
+                "a.c",
+                "@"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_response_file_errors() {
+        let tmp = TempDir::new("response-errors");
+        tmp.write("self.rsp", "-DA @self.rsp");
+        tmp.write("a.rsp", "@b.rsp");
+        tmp.write("b.rsp", "@a.rsp");
+        tmp.write("deep.rsp", "@deep1.rsp");
+        tmp.write("deep1.rsp", "@deep2.rsp");
+        tmp.write("deep2.rsp", "-DDEEP");
+        tmp.write("twice.rsp", "@deep2.rsp @deep2.rsp");
+        let expand = |args: &[&str], max_depth| {
+            let options = ResponseFileOptions {
+                max_depth,
+                ..ResponseFileOptions::default()
+            };
+            expand_response_files(args, tmp.path(), &options)
+        };
+
+        assert!(matches!(
+            expand(&["gcc", "@missing.rsp"], 16),
+            Err(ResponseFileError::Io { path, source })
+                if path == tmp.path().join("missing.rsp") && source.kind() == io::ErrorKind::NotFound
+        ));
+        assert!(matches!(
+            expand(&["gcc", "@self.rsp"], 16),
+            Err(ResponseFileError::Cycle { path }) if path == tmp.path().join("self.rsp")
+        ));
+        assert!(matches!(
+            expand(&["gcc", "@a.rsp"], 16),
+            Err(ResponseFileError::Cycle { path }) if path == tmp.path().join("a.rsp")
+        ));
+        assert!(matches!(
+            expand(&["gcc", "@deep.rsp"], 2),
+            Err(ResponseFileError::TooDeep { path, max_depth: 2 })
+                if path == tmp.path().join("deep2.rsp")
+        ));
+        assert_eq!(expand(&["gcc", "@deep.rsp"], 3).unwrap(), ["gcc", "-DDEEP"]);
+        assert_eq!(
+            expand(&["gcc", "@twice.rsp"], 16).unwrap(),
+            ["gcc", "-DDEEP", "-DDEEP"]
+        );
+    }
+}
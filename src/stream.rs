@@ -0,0 +1,335 @@
+use std::io::{BufReader, Bytes, Read};
+use std::iter::Peekable;
+
+use serde_json::Value;
+
+use crate::database::parse_entry;
+use crate::{CompilationDatabase, CompileCommand, Error};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum State {
+    /// Before the opening `[`
+    Start,
+    /// Inside the top level array, after `count` entries
+    Entries { count: usize },
+    /// After the closing `]`, or after an unrecoverable error
+    Done,
+}
+
+/// An iterator over the entries of a compilation database, read one at a time
+/// from any [`Read`]
+///
+/// Only the entry currently being parsed is held in memory, so arbitrarily
+/// large databases can be processed. Entries are checked the same way as by
+/// [`CompilationDatabase::from_reader`]: an entry that does not follow the
+/// format yields an error and iteration continues with the next entry, while
+/// invalid JSON ends the iteration.
+pub struct CommandStream<R: Read> {
+    bytes: Peekable<Bytes<BufReader<R>>>,
+    state: State,
+    /// The 1-based position of the next byte, for error reporting
+    line: usize,
+    column: usize,
+    /// The text of the entry currently being read, reused between entries
+    buf: Vec<u8>,
+}
+
+impl<R: Read> CommandStream<R> {
+    /// Creates a stream reading a compilation database from `reader`. The reader
+    /// is buffered internally.
+    pub fn new(reader: R) -> Self {
+        Self {
+            bytes: BufReader::new(reader).bytes().peekable(),
+            state: State::Start,
+            line: 1,
+            column: 1,
+            buf: Vec::new(),
+        }
+    }
+
+    fn syntax_error(&self, message: &str) -> Error {
+        Error::Syntax {
+            line: self.line,
+            column: self.column,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, Error> {
+        match self.bytes.peek() {
+            None => Ok(None),
+            Some(Ok(b)) => Ok(Some(*b)),
+            Some(Err(_)) => match self.bytes.next() {
+                Some(Err(e)) => Err(Error::Io(e)),
+                _ => unreachable!("peeked an error"),
+            },
+        }
+    }
+
+    fn bump(&mut self) -> Option<u8> {
+        let b = self.bytes.next()?.ok()?;
+        if b == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(b)
+    }
+
+    /// Skips whitespace and returns the next byte without consuming it
+    fn peek_token(&mut self) -> Result<Option<u8>, Error> {
+        while let Some(b) = self.peek()? {
+            if !matches!(b, b' ' | b'\t' | b'\n' | b'\r') {
+                return Ok(Some(b));
+            }
+            self.bump();
+        }
+        Ok(None)
+    }
+
+    /// Consumes the next byte after any whitespace, which must be `expected`
+    fn expect(&mut self, expected: u8, message: &str) -> Result<(), Error> {
+        match self.peek_token()? {
+            Some(b) if b == expected => {
+                self.bump();
+                Ok(())
+            }
+            Some(_) => Err(self.syntax_error(message)),
+            None => Err(self.syntax_error("EOF while parsing a list")),
+        }
+    }
+
+    /// Reads the text of the next array element into `buf`
+    fn read_element(&mut self) -> Result<(), Error> {
+        self.buf.clear();
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+
+        loop {
+            let Some(b) = self.peek()? else {
+                return Err(self.syntax_error("EOF while parsing a value"));
+            };
+            if !in_string && depth == 0 && matches!(b, b',' | b']') {
+                return Ok(());
+            }
+            self.bump();
+            self.buf.push(b);
+
+            if in_string {
+                match b {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+            } else {
+                match b {
+                    b'"' => in_string = true,
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Reads the next entry, or returns `None` at the end of the array
+    fn next_entry(&mut self) -> Result<Option<CompileCommand>, Error> {
+        if self.state == State::Start {
+            match self.peek_token()? {
+                Some(b'[') => {
+                    self.bump();
+                }
+                Some(_) => {
+                    return Err(Error::Schema {
+                        index: None,
+                        field: None,
+                        message: String::from("expected an array of entries"),
+                    })
+                }
+                None => return Err(self.syntax_error("EOF while parsing a value")),
+            }
+            self.state = State::Entries { count: 0 };
+        }
+        let State::Entries { count } = self.state else {
+            return Ok(None);
+        };
+
+        if self.peek_token()? == Some(b']') {
+            self.bump();
+            self.state = State::Done;
+            if self.peek_token()?.is_some() {
+                return Err(self.syntax_error("trailing characters"));
+            }
+            return Ok(None);
+        }
+        if count > 0 {
+            self.expect(b',', "expected `,` or `]`")?;
+        }
+        if self.peek_token()? == Some(b']') {
+            return Err(self.syntax_error("trailing comma"));
+        }
+
+        let (line, column) = (self.line, self.column);
+        self.read_element()?;
+        self.state = State::Entries { count: count + 1 };
+
+        let value = serde_json::from_slice::<Value>(&self.buf).map_err(|e| {
+            // Report the error's position within the whole input
+            match Error::from(e) {
+                Error::Syntax {
+                    line: entry_line,
+                    column: entry_column,
+                    message,
+                } => Error::Syntax {
+                    line: line + entry_line - 1,
+                    column: if entry_line == 1 {
+                        column + entry_column - 1
+                    } else {
+                        entry_column
+                    },
+                    message,
+                },
+                e => e,
+            }
+        })?;
+        parse_entry(count, value).map(Some)
+    }
+}
+
+impl<R: Read> Iterator for CommandStream<R> {
+    type Item = Result<CompileCommand, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_entry() {
+            Ok(entry) => entry.map(Ok),
+            Err(e) => {
+                if !matches!(
+                    e,
+                    Error::Schema { index: Some(_), .. } | Error::MissingCommand { .. }
+                ) {
+                    self.state = State::Done;
+                }
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl CompilationDatabase {
+    /// Reads a compilation database from `reader`, keeping only the entries for
+    /// which `filter` returns `true`
+    ///
+    /// Entries are read one at a time with a [`CommandStream`], so rejected
+    /// entries are never all held in memory at once
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered, see [`CompilationDatabase::from_reader`]
+    pub fn from_reader_filtered<R, F>(reader: R, mut filter: F) -> Result<Self, Error>
+    where
+        R: Read,
+        F: FnMut(&CompileCommand) -> bool,
+    {
+        let mut db = Self::new();
+        for entry in CommandStream::new(reader) {
+            let entry = entry?;
+            if filter(&entry) {
+                db.push(entry);
+            }
+        }
+        Ok(db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    const DB: &str = r#"[
+  {"directory": "/proj/a", "file": "a.c", "arguments": ["cc", "-DX=\"],{\"", "a.c"]},
+  {"directory": "/proj/b", "file": "b.c", "command": "cc b.c"},
+  {"directory": "/proj/a", "file": "c.c", "command": "cc c.c", "output": "c.o"}
+]
+"#;
+
+    #[test]
+    fn it_streams_entries() {
+        let streamed = CommandStream::new(DB.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            streamed,
+            DB.parse::<CompilationDatabase>().unwrap().into_vec()
+        );
+        assert_eq!(CommandStream::new("[]".as_bytes()).count(), 0);
+        assert_eq!(CommandStream::new(" [ \n ] \n".as_bytes()).count(), 0);
+    }
+
+    #[test]
+    fn it_filters_entries() {
+        let db = CompilationDatabase::from_reader_filtered(DB.as_bytes(), |entry| {
+            entry.directory.starts_with("/proj/a")
+        })
+        .unwrap();
+
+        assert_eq!(db.len(), 2);
+        assert!(db.contains(Path::new("/proj/a/a.c")));
+        assert!(db.contains(Path::new("/proj/a/c.c")));
+        assert!(!db.contains(Path::new("/proj/b/b.c")));
+    }
+
+    #[test]
+    fn it_continues_after_schema_errors() {
+        let json = r#"[{"directory": "/p", "file": "a.c"}, 1, {"directory": "/p", "file": "b.c", "command": "cc"}]"#;
+        let results = CommandStream::new(json.as_bytes()).collect::<Vec<_>>();
+
+        assert!(matches!(
+            results.as_slice(),
+            [
+                Err(Error::MissingCommand { index: 0 }),
+                Err(Error::Schema { index: Some(1), .. }),
+                Ok(_)
+            ]
+        ));
+    }
+
+    #[test]
+    fn it_stops_at_syntax_errors() {
+        let cases: &[(&str, usize, usize)] = &[
+            ("", 1, 1),
+            ("[", 1, 2),
+            ("[{\"directory\": \"/p\"", 1, 20),
+            ("[{}\n{}]", 2, 1),
+            ("[{},]", 1, 5),
+            ("[]]", 1, 3),
+            ("[\n  {\"directory\": \"/p\",,}]", 2, 22),
+        ];
+
+        for (json, expected_line, expected_column) in cases {
+            let results = CommandStream::new(json.as_bytes()).collect::<Vec<_>>();
+            match results.last() {
+                Some(Err(Error::Syntax { line, column, .. })) => {
+                    assert_eq!(
+                        (*line, *column),
+                        (*expected_line, *expected_column),
+                        "streaming {json:?}"
+                    );
+                }
+                result => panic!("expected a syntax error streaming {json:?}, got {result:?}"),
+            }
+        }
+
+        assert!(matches!(
+            CommandStream::new("{}".as_bytes())
+                .collect::<Vec<_>>()
+                .as_slice(),
+            [Err(Error::Schema { index: None, .. })]
+        ));
+    }
+}
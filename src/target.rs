@@ -0,0 +1,411 @@
+use crate::{AddressModel, ArgsError, CompileCommand, Dialect, DriverInfo, ParsedCommand};
+
+/// The architecture family of a target
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Arch {
+    /// 32-bit x86, e.g. `i386` or `i686`
+    X86,
+    X86_64,
+    /// 32-bit ARM, e.g. `armv7` or `thumbv7em`
+    Arm,
+    /// 64-bit ARM, e.g. `aarch64` or `arm64`
+    Aarch64,
+    Riscv32,
+    Riscv64,
+    Mips,
+    Mips64,
+    PowerPc,
+    PowerPc64,
+    Wasm32,
+    Wasm64,
+    /// Any other architecture
+    Other,
+}
+
+impl Arch {
+    /// Classifies the architecture component of a target triple
+    #[must_use]
+    pub fn from_triple_arch(arch: &str) -> Self {
+        match arch {
+            "i386" | "i486" | "i586" | "i686" | "x86" => Self::X86,
+            "x86_64" | "x86_64h" | "amd64" => Self::X86_64,
+            _ if arch.starts_with("aarch64") || arch.starts_with("arm64") => Self::Aarch64,
+            _ if arch.starts_with("arm") || arch.starts_with("thumb") => Self::Arm,
+            "riscv32" => Self::Riscv32,
+            "riscv64" => Self::Riscv64,
+            _ if arch.starts_with("mips64") => Self::Mips64,
+            _ if arch.starts_with("mips") => Self::Mips,
+            _ if arch.starts_with("powerpc64") || arch.starts_with("ppc64") => Self::PowerPc64,
+            _ if arch.starts_with("powerpc") || arch.starts_with("ppc") => Self::PowerPc,
+            "wasm32" => Self::Wasm32,
+            "wasm64" => Self::Wasm64,
+            _ => Self::Other,
+        }
+    }
+
+    /// The pointer width of the architecture, if known
+    #[must_use]
+    pub const fn bits(self) -> Option<u32> {
+        match self {
+            Self::X86 | Self::Arm | Self::Riscv32 | Self::Mips | Self::PowerPc | Self::Wasm32 => {
+                Some(32)
+            }
+            Self::X86_64
+            | Self::Aarch64
+            | Self::Riscv64
+            | Self::Mips64
+            | Self::PowerPc64
+            | Self::Wasm64 => Some(64),
+            Self::Other => None,
+        }
+    }
+}
+
+/// Where the target triple of a [`TargetInfo`] came from
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum TargetSource {
+    /// `--target=` or `-target`
+    Flag,
+    /// A compiler name prefixed with a triple, such as `aarch64-linux-gnu-gcc`
+    ProgramName,
+    /// The host the crate was built for
+    Host,
+}
+
+/// The architecture and ABI an entry compiles for
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct TargetInfo {
+    /// The target triple, with its architecture adjusted by `-arch`, `-m32`,
+    /// `-m64` and `-march=`
+    pub triple: String,
+    pub arch: Arch,
+    /// Where the unadjusted triple came from
+    pub source: TargetSource,
+    /// The `-march=` value, e.g. `armv7-a` or `haswell`
+    pub march: Option<String>,
+    /// The `-mcpu=` value, e.g. `cortex-m4`
+    pub cpu: Option<String>,
+    /// The `-mabi=` value, e.g. `lp64d`, or else the triple's environment, e.g.
+    /// `gnueabihf`
+    pub abi: Option<String>,
+}
+
+/// Operating systems that may directly follow the architecture of a triple
+/// without a vendor, as in `aarch64-linux-gnu`
+const VENDORLESS_OS: &[&str] = &["linux", "none", "windows", "freebsd", "netbsd", "openbsd"];
+
+/// Returns the triple for the host the crate was built for
+#[must_use]
+pub fn host_triple() -> String {
+    let arch = match std::env::consts::ARCH {
+        "x86" => "i686",
+        arch => arch,
+    };
+    match std::env::consts::OS {
+        "linux" => format!("{arch}-unknown-linux-gnu"),
+        "macos" => format!("{arch}-apple-darwin"),
+        "windows" => format!("{arch}-pc-windows-msvc"),
+        os => format!("{arch}-unknown-{os}"),
+    }
+}
+
+/// Returns the environment component of a triple, e.g. `gnu` for both
+/// `x86_64-unknown-linux-gnu` and `aarch64-linux-gnu`
+fn environment(triple: &str) -> Option<&str> {
+    let components: Vec<&str> = triple.split('-').collect();
+    match components[..] {
+        [_, _, _, env, ..] => Some(env),
+        [_, os, env] if VENDORLESS_OS.contains(&os) => Some(env),
+        _ => None,
+    }
+}
+
+/// Replaces the architecture component of `triple`
+fn with_arch(triple: &str, arch: &str) -> String {
+    match triple.split_once('-') {
+        Some((_, rest)) => format!("{arch}-{rest}"),
+        None => arch.to_string(),
+    }
+}
+
+/// Returns the 32 or 64-bit variant of a triple's architecture, following
+/// clang's `-m32` and `-m64`
+pub(crate) fn arch_variant(arch: &str, model: AddressModel) -> Option<&'static str> {
+    let variant = match (model, Arch::from_triple_arch(arch)) {
+        (AddressModel::Bits32, Arch::X86_64) => "i386",
+        (AddressModel::Bits32, Arch::Aarch64) => "arm",
+        (AddressModel::Bits32, Arch::Riscv64) => "riscv32",
+        (AddressModel::Bits32, Arch::Mips64) if arch.ends_with("el") => "mipsel",
+        (AddressModel::Bits32, Arch::Mips64) => "mips",
+        (AddressModel::Bits32, Arch::PowerPc64) if arch.ends_with("le") => "powerpcle",
+        (AddressModel::Bits32, Arch::PowerPc64) => "powerpc",
+        (AddressModel::Bits32, Arch::Wasm64) => "wasm32",
+        (AddressModel::Bits64, Arch::X86) => "x86_64",
+        (AddressModel::Bits64, Arch::Arm) => "aarch64",
+        (AddressModel::Bits64, Arch::Riscv32) => "riscv64",
+        (AddressModel::Bits64, Arch::Mips) if arch.ends_with("el") => "mips64el",
+        (AddressModel::Bits64, Arch::Mips) => "mips64",
+        (AddressModel::Bits64, Arch::PowerPc) if arch.ends_with("le") => "powerpc64le",
+        (AddressModel::Bits64, Arch::PowerPc) => "powerpc64",
+        (AddressModel::Bits64, Arch::Wasm32) => "wasm64",
+        _ => return None,
+    };
+    Some(variant)
+}
+
+/// Returns the triple architecture selected by `-march=` on 32-bit ARM and
+/// RISC-V, e.g. `armv7` for `armv7-a+neon` and `riscv32` for `rv32imac`. Other
+/// architectures take a CPU or ISA revision that doesn't change the triple.
+fn march_arch(arch: Arch, march: &str) -> Option<String> {
+    match arch {
+        Arch::Arm if march.starts_with("armv") => {
+            let march = march.split('+').next().unwrap_or(march);
+            Some(march.replace('-', ""))
+        }
+        Arch::Riscv32 | Arch::Riscv64 if march.starts_with("rv32") => Some("riscv32".into()),
+        Arch::Riscv32 | Arch::Riscv64 if march.starts_with("rv64") => Some("riscv64".into()),
+        _ => None,
+    }
+}
+
+impl TargetInfo {
+    /// Determines the target of a parsed command, falling back to
+    /// [`host_triple`]. See [`TargetInfo::from_parsed_with_host`].
+    #[must_use]
+    pub fn from_parsed(parsed: &ParsedCommand) -> Self {
+        Self::from_parsed_with_host(parsed, &host_triple())
+    }
+
+    /// Determines the target of a parsed command
+    ///
+    /// The triple is the last `--target=` or `-target`, else the triple the
+    /// compiler's name is prefixed with, else `host`. MSVC style drivers
+    /// default to `<host arch>-pc-windows-msvc` instead of `host`. The triple's
+    /// architecture is then adjusted, in order:
+    ///
+    /// 1. The last `-arch` replaces it
+    /// 2. The last `-m32` or `-m64` selects its 32 or 64-bit variant, e.g.
+    ///    `i386` for `x86_64`
+    /// 3. The last `-march=` selects the revision of 32-bit ARM, e.g. `armv7`,
+    ///    or the width of RISC-V, e.g. `riscv32` for `rv32imac`
+    ///
+    /// The ABI is the last `-mabi=`, else the triple's environment.
+    #[must_use]
+    pub fn from_parsed_with_host(parsed: &ParsedCommand, host: &str) -> Self {
+        let driver = DriverInfo::from_program(parsed.program.as_deref().unwrap_or_default());
+        let (mut triple, source) = match (parsed.target(), driver.target) {
+            (Some(target), _) => (target.to_string(), TargetSource::Flag),
+            (None, Some(prefix)) => (prefix, TargetSource::ProgramName),
+            (None, None) if parsed.dialect == Dialect::Msvc => {
+                let arch = host.split('-').next().unwrap_or(host);
+                (format!("{arch}-pc-windows-msvc"), TargetSource::Host)
+            }
+            (None, None) => (host.to_string(), TargetSource::Host),
+        };
+
+        if let Some(arch) = parsed.apple_arch() {
+            triple = with_arch(&triple, arch);
+        }
+        let arch_of = |triple: &str| triple.split('-').next().unwrap_or_default().to_string();
+        if let Some(variant) = parsed
+            .address_model()
+            .and_then(|model| arch_variant(&arch_of(&triple), model))
+        {
+            triple = with_arch(&triple, variant);
+        }
+        let march = parsed.arch();
+        if let Some(arch) =
+            march.and_then(|march| march_arch(Arch::from_triple_arch(&arch_of(&triple)), march))
+        {
+            triple = with_arch(&triple, &arch);
+        }
+
+        Self {
+            arch: Arch::from_triple_arch(&arch_of(&triple)),
+            source,
+            march: march.map(ToString::to_string),
+            cpu: parsed.cpu().map(ToString::to_string),
+            abi: parsed
+                .abi()
+                .or_else(|| environment(&triple))
+                .map(ToString::to_string),
+            triple,
+        }
+    }
+}
+
+impl CompileCommand {
+    /// Determines the architecture and ABI the entry compiles for, see
+    /// [`TargetInfo::from_parsed_with_host`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument vector cannot be determined, see
+    /// [`CompileCommand::args`]
+    pub fn target_info(&self) -> Result<TargetInfo, ArgsError> {
+        Ok(TargetInfo::from_parsed(&self.parse()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOST: &str = "x86_64-unknown-linux-gnu";
+
+    #[test]
+    fn it_resolves_cross_compile_targets() {
+        type Case<'a> = (&'a str, &'a str, Arch, TargetSource, Option<&'a str>);
+        let cases: &[Case] = &[
+            (
+                "gcc -c a.c",
+                HOST,
+                Arch::X86_64,
+                TargetSource::Host,
+                Some("gnu"),
+            ),
+            (
+                "gcc -m32 -c a.c",
+                "i386-unknown-linux-gnu",
+                Arch::X86,
+                TargetSource::Host,
+                Some("gnu"),
+            ),
+            (
+                "gcc -march=haswell -c a.c",
+                HOST,
+                Arch::X86_64,
+                TargetSource::Host,
+                Some("gnu"),
+            ),
+            (
+                "aarch64-linux-gnu-gcc-12 -O2 -c a.c",
+                "aarch64-linux-gnu",
+                Arch::Aarch64,
+                TargetSource::ProgramName,
+                Some("gnu"),
+            ),
+            (
+                "arm-linux-gnueabihf-g++ -march=armv7-a+neon -mfpu=neon -c a.cc",
+                "armv7a-linux-gnueabihf",
+                Arch::Arm,
+                TargetSource::ProgramName,
+                Some("gnueabihf"),
+            ),
+            (
+                "arm-none-eabi-gcc -mcpu=cortex-m4 -mthumb -mfloat-abi=hard -c a.c",
+                "arm-none-eabi",
+                Arch::Arm,
+                TargetSource::ProgramName,
+                Some("eabi"),
+            ),
+            (
+                "clang --target=riscv64-unknown-linux-gnu -march=rv64gc -mabi=lp64d -c a.c",
+                "riscv64-unknown-linux-gnu",
+                Arch::Riscv64,
+                TargetSource::Flag,
+                Some("lp64d"),
+            ),
+            (
+                "riscv64-unknown-elf-gcc -march=rv32imac -mabi=ilp32 -c a.c",
+                "riscv32-unknown-elf",
+                Arch::Riscv32,
+                TargetSource::ProgramName,
+                Some("ilp32"),
+            ),
+            (
+                "aarch64-linux-gnu-clang --target=x86_64-pc-windows-gnu -c a.c",
+                "x86_64-pc-windows-gnu",
+                Arch::X86_64,
+                TargetSource::Flag,
+                Some("gnu"),
+            ),
+            (
+                "clang -target aarch64-linux-android21 -c a.c",
+                "aarch64-linux-android21",
+                Arch::Aarch64,
+                TargetSource::Flag,
+                Some("android21"),
+            ),
+            (
+                "clang -target x86_64-apple-darwin -arch arm64 -c a.c",
+                "arm64-apple-darwin",
+                Arch::Aarch64,
+                TargetSource::Flag,
+                None,
+            ),
+            (
+                "x86_64-w64-mingw32-g++ -m32 -c a.cc",
+                "i386-w64-mingw32",
+                Arch::X86,
+                TargetSource::ProgramName,
+                None,
+            ),
+            (
+                "mips-linux-gnu-gcc -m64 -mabi=64 -c a.c",
+                "mips64-linux-gnu",
+                Arch::Mips64,
+                TargetSource::ProgramName,
+                Some("64"),
+            ),
+            (
+                "powerpc64le-linux-gnu-gcc -mcpu=power9 -c a.c",
+                "powerpc64le-linux-gnu",
+                Arch::PowerPc64,
+                TargetSource::ProgramName,
+                Some("gnu"),
+            ),
+            (
+                "clang --target=wasm32-unknown-unknown -c a.c",
+                "wasm32-unknown-unknown",
+                Arch::Wasm32,
+                TargetSource::Flag,
+                None,
+            ),
+            (
+                "cl.exe /c a.c",
+                "x86_64-pc-windows-msvc",
+                Arch::X86_64,
+                TargetSource::Host,
+                Some("msvc"),
+            ),
+            (
+                "clang-cl -m32 /c a.c",
+                "i386-pc-windows-msvc",
+                Arch::X86,
+                TargetSource::Host,
+                Some("msvc"),
+            ),
+        ];
+
+        for (command, triple, arch, source, abi) in cases {
+            let args = crate::tokenize_command(command).unwrap();
+            let info = TargetInfo::from_parsed_with_host(&ParsedCommand::parse(&args), HOST);
+            assert_eq!(info.triple, *triple, "resolving {command:?}");
+            assert_eq!(info.arch, *arch, "resolving {command:?}");
+            assert_eq!(info.source, *source, "resolving {command:?}");
+            assert_eq!(info.abi.as_deref(), *abi, "resolving {command:?}");
+        }
+    }
+
+    #[test]
+    fn it_records_cpu_and_march() {
+        let args = ["arm-none-eabi-gcc", "-mcpu=cortex-m7", "-march=armv7e-m+fp"];
+        let info = TargetInfo::from_parsed_with_host(&ParsedCommand::parse(&args), HOST);
+
+        assert_eq!(info.triple, "armv7em-none-eabi");
+        assert_eq!(info.cpu.as_deref(), Some("cortex-m7"));
+        assert_eq!(info.march.as_deref(), Some("armv7e-m+fp"));
+    }
+
+    #[test]
+    fn it_resolves_entry_targets() {
+        let db = r#"[{"directory": "/p", "file": "a.c", "command": "ccache aarch64-linux-gnu-gcc -c a.c"}]"#
+            .parse::<crate::CompilationDatabase>()
+            .unwrap();
+        let info = db[0].target_info().unwrap();
+
+        assert_eq!(info.triple, "aarch64-linux-gnu");
+        assert_eq!(info.arch, Arch::Aarch64);
+    }
+}
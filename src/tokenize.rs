@@ -0,0 +1,742 @@
+use std::fmt::{self, Display};
+
+use crate::DriverInfo;
+
+/// Errors that can occur while splitting a `command` string into arguments
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum TokenizeError {
+    /// A quote opened at the contained byte offset is never closed
+    UnterminatedQuote { position: usize },
+    /// The backslash at the contained byte offset is the last character of the
+    /// command, so there is nothing for it to escape
+    TrailingBackslash { position: usize },
+}
+
+impl Display for TokenizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnterminatedQuote { position } => {
+                write!(f, "unterminated quote starting at offset {position}")
+            }
+            Self::TrailingBackslash { position } => {
+                write!(f, "trailing backslash at offset {position}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TokenizeError {}
+
+/// The quoting convention used to split a `command` string into arguments
+#[derive(Debug, Clone, Copy, Default, Hash, Eq, PartialEq)]
+pub enum TokenizeMode {
+    /// The JSON Compilation Database rules, where ‘"’ and ‘\’ are the only
+    /// special characters
+    #[default]
+    Spec,
+    /// POSIX shell quoting, with single quotes, double quotes and backslash
+    /// escapes. Expansions such as `$VAR` and globs are not performed.
+    Posix,
+    /// The rules of Windows' `CommandLineToArgvW`, as used by MSVC and
+    /// clang-cl. Only spaces and tabs separate arguments.
+    Windows,
+}
+
+impl TokenizeMode {
+    /// Guesses the quoting convention a command was written with from its
+    /// program name, i.e. `arguments[0]` or the first word of `command`
+    ///
+    /// MSVC style drivers (`cl`, `clang-cl`), `.exe` programs and Windows style
+    /// paths select `Windows`, shells select `Posix`, and everything else
+    /// selects `Spec`
+    #[must_use]
+    pub fn detect(program: &str) -> Self {
+        let is_windows_path = program.contains('\\')
+            || program
+                .as_bytes()
+                .get(..2)
+                .is_some_and(|prefix| prefix[0].is_ascii_alphabetic() && prefix[1] == b':');
+        let name = program
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(program)
+            .to_ascii_lowercase();
+        let is_exe = name.ends_with(".exe");
+
+        if DriverInfo::from_program(program).driver.is_msvc_like() || is_exe || is_windows_path {
+            return Self::Windows;
+        }
+        match name.as_str() {
+            "sh" | "bash" | "dash" | "zsh" | "ksh" => Self::Posix,
+            _ => Self::Spec,
+        }
+    }
+}
+
+/// The characters that separate arguments in a `command` string
+const fn is_separator(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\x0B' | '\x0C' | '\r')
+}
+
+/// The characters that separate arguments for `CommandLineToArgvW`, which
+/// treats other whitespace, such as newlines, as part of an argument
+const fn is_windows_separator(c: char) -> bool {
+    matches!(c, ' ' | '\t')
+}
+
+/// Returns the first word of `cmd`, which names the program being invoked
+pub(crate) fn program_name(cmd: &str) -> &str {
+    let cmd = cmd.trim_start_matches(is_separator);
+    match cmd.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next().unwrap_or(quoted),
+        None => cmd.split(is_separator).next().unwrap_or(cmd),
+    }
+}
+
+/// Splits a `command` string into its arguments following the JSON Compilation
+/// Database rules, where ‘"’ and ‘\’ are the only special characters
+///
+/// - Unquoted whitespace separates arguments
+/// - A backslash causes the following character to be taken literally, both
+///   inside and outside of quotes
+/// - Double quotes group characters, including whitespace, into a single
+///   argument and are removed from the result. `""` produces an empty argument.
+///
+/// # Errors
+///
+/// Returns an error if a quote is never closed or the command ends with an
+/// unescaped backslash
+pub fn tokenize_command(cmd: &str) -> Result<Vec<String>, TokenizeError> {
+    tokenize_command_with(cmd, TokenizeMode::Spec)
+}
+
+/// Splits a `command` string into its arguments following the given `mode`'s
+/// quoting rules
+///
+/// # Errors
+///
+/// Returns an error if a quote is never closed or the command ends with an
+/// unescaped backslash. `TokenizeMode::Windows` never fails, as
+/// `CommandLineToArgvW` implicitly closes any open quote.
+pub fn tokenize_command_with(cmd: &str, mode: TokenizeMode) -> Result<Vec<String>, TokenizeError> {
+    match mode {
+        TokenizeMode::Spec => tokenize_spec(cmd),
+        TokenizeMode::Posix => tokenize_posix(cmd),
+        TokenizeMode::Windows => Ok(tokenize_windows(cmd, true)),
+    }
+}
+
+fn tokenize_spec(cmd: &str) -> Result<Vec<String>, TokenizeError> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = cmd.char_indices();
+
+    while let Some((position, c)) = chars.next() {
+        match c {
+            '\\' => {
+                let (_, escaped) = chars
+                    .next()
+                    .ok_or(TokenizeError::TrailingBackslash { position })?;
+                current.push(escaped);
+                in_arg = true;
+            }
+            '"' => {
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => {
+                            let (_, escaped) = chars
+                                .next()
+                                .ok_or(TokenizeError::UnterminatedQuote { position })?;
+                            current.push(escaped);
+                        }
+                        Some((_, quoted)) => current.push(quoted),
+                        None => return Err(TokenizeError::UnterminatedQuote { position }),
+                    }
+                }
+                in_arg = true;
+            }
+            c if is_separator(c) => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+
+    Ok(args)
+}
+
+fn tokenize_posix(cmd: &str) -> Result<Vec<String>, TokenizeError> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = cmd.char_indices().peekable();
+
+    while let Some((position, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                // A backslash-newline pair is a line continuation
+                Some((_, '\n')) => {}
+                Some((_, escaped)) => {
+                    current.push(escaped);
+                    in_arg = true;
+                }
+                None => return Err(TokenizeError::TrailingBackslash { position }),
+            },
+            '\'' => {
+                loop {
+                    match chars.next() {
+                        Some((_, '\'')) => break,
+                        Some((_, quoted)) => current.push(quoted),
+                        None => return Err(TokenizeError::UnterminatedQuote { position }),
+                    }
+                }
+                in_arg = true;
+            }
+            '"' => {
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.peek() {
+                            Some((_, '\n')) => {
+                                chars.next();
+                            }
+                            Some(&(_, escaped @ ('$' | '`' | '"' | '\\'))) => {
+                                current.push(escaped);
+                                chars.next();
+                            }
+                            _ => current.push('\\'),
+                        },
+                        Some((_, quoted)) => current.push(quoted),
+                        None => return Err(TokenizeError::UnterminatedQuote { position }),
+                    }
+                }
+                in_arg = true;
+            }
+            c if is_separator(c) => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+
+    Ok(args)
+}
+
+/// Splits `cmd` following the rules of `CommandLineToArgvW`. If
+/// `program_name_rules` is set, the first argument is read as a program name,
+/// which ends at the next quote, space or tab and contains no escapes.
+pub(crate) fn tokenize_windows(cmd: &str, program_name_rules: bool) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut chars = cmd
+        .trim_start_matches(is_windows_separator)
+        .chars()
+        .peekable();
+
+    if program_name_rules && chars.peek().is_some() {
+        let mut program = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            program.extend(chars.by_ref().take_while(|&c| c != '"'));
+        } else {
+            while let Some(c) = chars.next_if(|&c| !is_windows_separator(c)) {
+                program.push(c);
+            }
+        }
+        args.push(program);
+    }
+
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut in_quotes = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let mut backslashes = 1;
+                while chars.next_if_eq(&'\\').is_some() {
+                    backslashes += 1;
+                }
+                if chars.peek() == Some(&'"') {
+                    // 2n backslashes followed by a quote produce n backslashes,
+                    // and the quote is handled normally. 2n + 1 backslashes
+                    // produce n backslashes and a literal quote.
+                    current.push_str(&"\\".repeat(backslashes / 2));
+                    if backslashes % 2 == 1 {
+                        current.push('"');
+                        chars.next();
+                    }
+                } else {
+                    current.push_str(&"\\".repeat(backslashes));
+                }
+                in_arg = true;
+            }
+            '"' => {
+                // Inside of quotes, a doubled quote produces a literal quote
+                if in_quotes && chars.next_if_eq(&'"').is_some() {
+                    current.push('"');
+                } else {
+                    in_quotes = !in_quotes;
+                }
+                in_arg = true;
+            }
+            c if is_windows_separator(c) && !in_quotes => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+/// Renders `args` as a single `command` string that [`tokenize_command_with`]
+/// splits back into exactly the same arguments when given the same `mode`
+///
+/// Arguments are only quoted when necessary. With `TokenizeMode::Windows` the
+/// first argument is subject to the program name rules of `CommandLineToArgvW`,
+/// which cannot represent a ‘"’, so such a program name will not round trip.
+#[must_use]
+pub fn join_command<S: AsRef<str>>(args: &[S], mode: TokenizeMode) -> String {
+    let mut cmd = String::new();
+
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            cmd.push(' ');
+        }
+        let arg = arg.as_ref();
+        match mode {
+            TokenizeMode::Spec => quote_spec(arg, &mut cmd),
+            TokenizeMode::Posix => quote_posix(arg, &mut cmd),
+            TokenizeMode::Windows if i == 0 && !arg.contains('"') => {
+                if arg.is_empty() || arg.contains(is_separator) {
+                    cmd.push('"');
+                    cmd.push_str(arg);
+                    cmd.push('"');
+                } else {
+                    cmd.push_str(arg);
+                }
+            }
+            TokenizeMode::Windows => quote_windows(arg, &mut cmd),
+        }
+    }
+
+    cmd
+}
+
+fn quote_spec(arg: &str, cmd: &mut String) {
+    if !arg.is_empty() && !arg.contains(|c| is_separator(c) || c == '"' || c == '\\') {
+        cmd.push_str(arg);
+        return;
+    }
+
+    cmd.push('"');
+    for c in arg.chars() {
+        if c == '"' || c == '\\' {
+            cmd.push('\\');
+        }
+        cmd.push(c);
+    }
+    cmd.push('"');
+}
+
+fn quote_posix(arg: &str, cmd: &mut String) {
+    let is_safe = |c: char| {
+        c.is_ascii_alphanumeric()
+            || matches!(c, '_' | '-' | '+' | '=' | '/' | '.' | ',' | ':' | '@' | '%')
+    };
+    if !arg.is_empty() && arg.chars().all(is_safe) {
+        cmd.push_str(arg);
+        return;
+    }
+
+    cmd.push('\'');
+    for c in arg.chars() {
+        if c == '\'' {
+            // Close the quote, emit an escaped quote, then reopen
+            cmd.push_str("'\\''");
+        } else {
+            cmd.push(c);
+        }
+    }
+    cmd.push('\'');
+}
+
+fn quote_windows(arg: &str, cmd: &mut String) {
+    if !arg.is_empty() && !arg.contains(|c| is_separator(c) || c == '"') {
+        cmd.push_str(arg);
+        return;
+    }
+
+    cmd.push('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // Escape the pending backslashes and the quote itself
+                cmd.push_str(&"\\".repeat(backslashes * 2 + 1));
+                cmd.push('"');
+                backslashes = 0;
+            }
+            c => {
+                cmd.push_str(&"\\".repeat(backslashes));
+                cmd.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    // Backslashes before the closing quote must be escaped
+    cmd.push_str(&"\\".repeat(backslashes * 2));
+    cmd.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_tokenizes_spec_commands() {
+        let cases: &[(&str, &[&str])] = &[
+            ("", &[]),
+            ("   \t\n ", &[]),
+            ("cc", &["cc"]),
+            ("  cc  ", &["cc"]),
+            ("cc -c main.c", &["cc", "-c", "main.c"]),
+            ("cc\t-c\r\nmain.c", &["cc", "-c", "main.c"]),
+            ("cc\x0B-c\x0Cmain.c", &["cc", "-c", "main.c"]),
+            ("cc    -c     main.c", &["cc", "-c", "main.c"]),
+            (r#""foo bar""#, &["foo bar"]),
+            (r#"a"b c"d"#, &["ab cd"]),
+            (r#""a""b""#, &["ab"]),
+            (r#""""#, &[""]),
+            (r#"cc "" main.c"#, &["cc", "", "main.c"]),
+            (r#"cc """#, &["cc", ""]),
+            (r"a\ b", &["a b"]),
+            (r"a\\b", &[r"a\b"]),
+            (r#"a\"b"#, &[r#"a"b"#]),
+            (r"\a", &["a"]),
+            (r"\\", &[r"\"]),
+            (r#""a\"b""#, &[r#"a"b"#]),
+            (r#""a\\b""#, &[r"a\b"]),
+            (r#""a\b""#, &["ab"]),
+            (r#""\\\"""#, &[r#"\""#]),
+            (r#""it's""#, &["it's"]),
+            ("'a b'", &["'a", "b'"]),
+            ("$HOME *.c", &["$HOME", "*.c"]),
+            (
+                r#"-DSOMEDEF="With spaces, quotes and \\-es.""#,
+                &[r"-DSOMEDEF=With spaces, quotes and \-es."],
+            ),
+            (r#"-DSTR=\"value\""#, &[r#"-DSTR="value""#]),
+            (
+                r#"-DSTR="\"value with spaces\"""#,
+                &[r#"-DSTR="value with spaces""#],
+            ),
+            (
+                r#"-I"C:\\Program Files\\inc""#,
+                &[r"-IC:\Program Files\inc"],
+            ),
+            ("é ü", &["é", "ü"]),
+            ("\"é ü\"", &["é ü"]),
+            (r"\é", &["é"]),
+        ];
+
+        for (cmd, expected) in cases {
+            let actual = tokenize_command(cmd).unwrap();
+            assert_eq!(&actual, expected, "tokenizing {cmd:?}");
+        }
+    }
+
+    #[test]
+    fn it_reports_tokenize_errors() {
+        let cases: &[(&str, TokenizeError)] = &[
+            ("\"", TokenizeError::UnterminatedQuote { position: 0 }),
+            (
+                "cc \"main.c",
+                TokenizeError::UnterminatedQuote { position: 3 },
+            ),
+            (
+                "cc \"a\" \"b",
+                TokenizeError::UnterminatedQuote { position: 7 },
+            ),
+            (
+                "cc \"main.c\\",
+                TokenizeError::UnterminatedQuote { position: 3 },
+            ),
+            (
+                "cc \"main.c\\\"",
+                TokenizeError::UnterminatedQuote { position: 3 },
+            ),
+            ("\\", TokenizeError::TrailingBackslash { position: 0 }),
+            (
+                "cc main.c\\",
+                TokenizeError::TrailingBackslash { position: 9 },
+            ),
+            ("é\\", TokenizeError::TrailingBackslash { position: 2 }),
+        ];
+
+        for (cmd, expected) in cases {
+            assert_eq!(tokenize_command(cmd), Err(*expected), "tokenizing {cmd:?}");
+        }
+    }
+
+    #[test]
+    fn it_tokenizes_posix_commands() {
+        let cases: &[(&str, &[&str])] = &[
+            ("", &[]),
+            ("cc -c main.c", &["cc", "-c", "main.c"]),
+            ("'a b' c", &["a b", "c"]),
+            (r"'a\b'", &[r"a\b"]),
+            (r#"'a"b'"#, &[r#"a"b"#]),
+            ("''", &[""]),
+            ("a'b'\"c\"d", &["abcd"]),
+            (r#""a b""#, &["a b"]),
+            (r#""it's""#, &["it's"]),
+            (r#""a\"b""#, &[r#"a"b"#]),
+            (r#""a\\b""#, &[r"a\b"]),
+            (r#""a\$b""#, &["a$b"]),
+            (r#""a\`b""#, &["a`b"]),
+            (r#""a\b""#, &[r"a\b"]),
+            (r#""a\-b""#, &[r"a\-b"]),
+            ("\"a\\\nb\"", &["ab"]),
+            (r"a\ b", &["a b"]),
+            (r"a\'b", &["a'b"]),
+            (r"a\-b", &["a-b"]),
+            ("a\\\nb", &["ab"]),
+            ("a \\\n b", &["a", "b"]),
+            ("$HOME *.c ~", &["$HOME", "*.c", "~"]),
+            (
+                r#"cc '-DMSG="hello world"' -DX=\"y\" main.c"#,
+                &["cc", r#"-DMSG="hello world""#, r#"-DX="y""#, "main.c"],
+            ),
+        ];
+
+        for (cmd, expected) in cases {
+            let actual = tokenize_command_with(cmd, TokenizeMode::Posix).unwrap();
+            assert_eq!(&actual, expected, "tokenizing {cmd:?}");
+        }
+    }
+
+    #[test]
+    fn it_reports_posix_tokenize_errors() {
+        let cases: &[(&str, TokenizeError)] = &[
+            ("'", TokenizeError::UnterminatedQuote { position: 0 }),
+            ("cc 'a\\", TokenizeError::UnterminatedQuote { position: 3 }),
+            ("cc \"a", TokenizeError::UnterminatedQuote { position: 3 }),
+            (
+                "cc \"a\\\"",
+                TokenizeError::UnterminatedQuote { position: 3 },
+            ),
+            ("cc \\", TokenizeError::TrailingBackslash { position: 3 }),
+        ];
+
+        for (cmd, expected) in cases {
+            assert_eq!(
+                tokenize_command_with(cmd, TokenizeMode::Posix),
+                Err(*expected),
+                "tokenizing {cmd:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_tokenizes_windows_commands() {
+        let cases: &[(&str, &[&str])] = &[
+            ("", &[]),
+            ("   ", &[]),
+            ("cl.exe /c main.c", &["cl.exe", "/c", "main.c"]),
+            (
+                r#""C:\Program Files\LLVM\bin\clang-cl.exe" /c main.c"#,
+                &[r"C:\Program Files\LLVM\bin\clang-cl.exe", "/c", "main.c"],
+            ),
+            (r"C:\tools\cl.exe main.c", &[r"C:\tools\cl.exe", "main.c"]),
+            (r#"a\"b c"#, &[r#"a\"b"#, "c"]),
+            (r#""" a"#, &["", "a"]),
+            (r#"cl "a b" c"#, &["cl", "a b", "c"]),
+            (r#"cl a"b c"d"#, &["cl", "ab cd"]),
+            (r#"cl """#, &["cl", ""]),
+            (r"cl a\b", &["cl", r"a\b"]),
+            (r"cl a\\b", &["cl", r"a\\b"]),
+            (r#"cl a\"b"#, &["cl", r#"a"b"#]),
+            (r#"cl a\\"b c""#, &["cl", r"a\b c"]),
+            (r#"cl a\\\"b"#, &["cl", r#"a\"b"#]),
+            (r#"cl "a\\" b"#, &["cl", r"a\", "b"]),
+            (r#"cl "C:\dir\\" b"#, &["cl", r"C:\dir\", "b"]),
+            (r#"cl "a""b" c"#, &["cl", r#"a"b"#, "c"]),
+            (r#"cl "a"""#, &["cl", r#"a""#]),
+            (r#"cl "a b"#, &["cl", "a b"]),
+            (r"cl trailing\", &["cl", r"trailing\"]),
+            ("cl 'a b'", &["cl", "'a", "b'"]),
+            ("cl\ta \t b", &["cl", "a", "b"]),
+            ("cl a\nb\r\n", &["cl", "a\nb\r\n"]),
+            ("cl\nx a\x0Bb\x0Cc", &["cl\nx", "a\x0Bb\x0Cc"]),
+            (
+                r#"cl /DMSG=\"hello\" "/IC:\Program Files\inc" /Fo"out dir\\""#,
+                &[
+                    "cl",
+                    r#"/DMSG="hello""#,
+                    r"/IC:\Program Files\inc",
+                    r"/Foout dir\",
+                ],
+            ),
+        ];
+
+        for (cmd, expected) in cases {
+            let actual = tokenize_command_with(cmd, TokenizeMode::Windows).unwrap();
+            assert_eq!(&actual, expected, "tokenizing {cmd:?}");
+        }
+    }
+
+    #[test]
+    fn it_detects_tokenize_modes() {
+        let cases: &[(&str, TokenizeMode)] = &[
+            ("/usr/bin/clang++", TokenizeMode::Spec),
+            ("gcc", TokenizeMode::Spec),
+            ("aarch64-linux-gnu-gcc-12", TokenizeMode::Spec),
+            ("cl", TokenizeMode::Windows),
+            ("CL.EXE", TokenizeMode::Windows),
+            ("clang-cl", TokenizeMode::Windows),
+            ("/usr/bin/clang-cl", TokenizeMode::Windows),
+            ("clang++.exe", TokenizeMode::Windows),
+            (r"C:\LLVM\bin\clang.exe", TokenizeMode::Windows),
+            ("C:/mingw/bin/gcc", TokenizeMode::Windows),
+            ("/bin/sh", TokenizeMode::Posix),
+            ("bash", TokenizeMode::Posix),
+            ("", TokenizeMode::Spec),
+        ];
+
+        for (program, expected) in cases {
+            assert_eq!(
+                TokenizeMode::detect(program),
+                *expected,
+                "detecting {program:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_joins_commands() {
+        let args = ["cc", "-DMSG=\"a b\"", r"C:\dir\", "", "it's", "plain"];
+        let cases: &[(TokenizeMode, &str)] = &[
+            (
+                TokenizeMode::Spec,
+                r#"cc "-DMSG=\"a b\"" "C:\\dir\\" "" it's plain"#,
+            ),
+            (
+                TokenizeMode::Posix,
+                r#"cc '-DMSG="a b"' 'C:\dir\' '' 'it'\''s' plain"#,
+            ),
+            (
+                TokenizeMode::Windows,
+                r#"cc "-DMSG=\"a b\"" C:\dir\ "" it's plain"#,
+            ),
+        ];
+
+        for (mode, expected) in cases {
+            assert_eq!(join_command(&args, *mode), *expected, "joining in {mode:?}");
+        }
+        assert_eq!(
+            join_command(&[r"C:\Program Files\cl.exe", "/c"], TokenizeMode::Windows),
+            r#""C:\Program Files\cl.exe" /c"#
+        );
+        assert_eq!(join_command::<&str>(&[], TokenizeMode::Spec), "");
+    }
+
+    /// A small xorshift generator, so the round trip properties can be checked
+    /// against many reproducible inputs without extra dependencies
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+
+        fn arg(&mut self, alphabet: &[char]) -> String {
+            let len = self.below(8);
+            (0..len)
+                .map(|_| alphabet[self.below(alphabet.len())])
+                .collect()
+        }
+    }
+
+    const ALPHABET: &[char] = &[
+        'a', 'Z', '0', '-', '=', '/', ' ', '\t', '\n', '\r', '"', '\\', '\'', '$', '`', '*', 'é',
+    ];
+
+    #[test]
+    fn it_round_trips_joined_commands() {
+        let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+
+        for mode in [
+            TokenizeMode::Spec,
+            TokenizeMode::Posix,
+            TokenizeMode::Windows,
+        ] {
+            for _ in 0..2000 {
+                let count = rng.below(6);
+                let mut args: Vec<String> = (0..count).map(|_| rng.arg(ALPHABET)).collect();
+                if mode == TokenizeMode::Windows {
+                    if let Some(program) = args.first_mut() {
+                        program.retain(|c| c != '"');
+                    }
+                }
+
+                let cmd = join_command(&args, mode);
+                assert_eq!(
+                    tokenize_command_with(&cmd, mode).unwrap(),
+                    args,
+                    "round tripping {cmd:?} in {mode:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_finds_program_names() {
+        assert_eq!(program_name("  gcc -c main.c"), "gcc");
+        assert_eq!(
+            program_name(r#""C:\Program Files\cl.exe" /c"#),
+            r"C:\Program Files\cl.exe"
+        );
+        assert_eq!(program_name(""), "");
+    }
+}
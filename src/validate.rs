@@ -0,0 +1,347 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::path::Path;
+
+use crate::path::resolve;
+use crate::{
+    ArgsError, ArgsOptions, CompilationDatabase, CompileArgs, CompileCommand, ConflictPolicy,
+    ParsedCommand, SourceFile,
+};
+
+/// How serious a [`Diagnostic`] is
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Severity {
+    /// The entry is usable, but is likely to confuse tools
+    Warning,
+    /// The entry violates the JSON Compilation Database format
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found by [`CompilationDatabase::validate`]
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The index of the offending entry
+    pub index: usize,
+    /// The offending field, or `None` if the entry as a whole is at fault
+    pub field: Option<&'static str>,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: entry {}", self.severity, self.index)?;
+        if let Some(field) = self.field {
+            write!(f, ", field `{field}`")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl CompilationDatabase {
+    /// Checks every entry against the JSON Compilation Database rules
+    ///
+    /// Errors are reported for entries with an empty `directory` or `file`,
+    /// without `arguments` or `command`, or with an empty or unparseable argument
+    /// vector. Warnings are reported for a relative `directory`, a `file` that
+    /// doesn't appear in the arguments, an `output` that doesn't match `-o`,
+    /// `arguments` and `command` that disagree, and duplicate entries.
+    ///
+    /// Diagnostics are ordered by entry index
+    #[must_use]
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut seen = HashMap::new();
+
+        for (index, entry) in self.iter().enumerate() {
+            let mut report = |severity, field, message: String| {
+                diagnostics.push(Diagnostic {
+                    severity,
+                    index,
+                    field,
+                    message,
+                });
+            };
+
+            validate_entry(entry, &mut report);
+            match seen.entry(entry) {
+                Entry::Occupied(first) => report(
+                    Severity::Warning,
+                    None,
+                    format!("duplicate of entry {}", first.get()),
+                ),
+                Entry::Vacant(slot) => {
+                    slot.insert(index);
+                }
+            }
+        }
+
+        diagnostics
+    }
+}
+
+fn validate_entry(
+    entry: &CompileCommand,
+    report: &mut impl FnMut(Severity, Option<&'static str>, String),
+) {
+    if entry.directory.as_os_str().is_empty() {
+        report(
+            Severity::Error,
+            Some("directory"),
+            String::from("`directory` is empty"),
+        );
+    } else if entry.directory.is_relative() {
+        report(
+            Severity::Warning,
+            Some("directory"),
+            format!("`{}` is not an absolute path", entry.directory.display()),
+        );
+    }
+
+    if matches!(&entry.file, SourceFile::File(file) if file.as_os_str().is_empty()) {
+        report(
+            Severity::Error,
+            Some("file"),
+            String::from("`file` is empty"),
+        );
+    }
+
+    if let Some(CompileArgs::Arguments(args)) = &entry.arguments {
+        if args.is_empty() {
+            report(
+                Severity::Error,
+                Some("arguments"),
+                String::from("`arguments` is empty"),
+            );
+        }
+    }
+    match entry.args_from_cmd_with(entry.tokenize_mode()) {
+        Some(Err(e)) => report(Severity::Error, Some("command"), e.to_string()),
+        Some(Ok(args)) if args.is_empty() => report(
+            Severity::Error,
+            Some("command"),
+            String::from("`command` is empty"),
+        ),
+        _ => {}
+    }
+
+    let options = ArgsOptions {
+        conflict: ConflictPolicy::Error,
+        ..ArgsOptions::default()
+    };
+    let args = match entry.args_with(&options) {
+        Ok(args) if !args.is_empty() => args,
+        Ok(_) | Err(ArgsError::Tokenize(_)) => return,
+        Err(ArgsError::Missing) => {
+            report(
+                Severity::Error,
+                None,
+                String::from("one of `arguments` or `command` is required"),
+            );
+            return;
+        }
+        Err(ArgsError::Conflict { .. }) => {
+            report(
+                Severity::Warning,
+                None,
+                String::from("`arguments` and `command` disagree"),
+            );
+            match entry.args() {
+                Ok(args) => args,
+                Err(_) => return,
+            }
+        }
+    };
+
+    let resolve_arg = |arg: &str| resolve(&entry.directory, Path::new(arg));
+    if let Some(file) = entry.resolved_file() {
+        if !args.iter().skip(1).any(|arg| resolve_arg(arg) == file) {
+            report(
+                Severity::Warning,
+                Some("file"),
+                format!("`{}` does not appear in the arguments", file.display()),
+            );
+        }
+    }
+
+    let parsed = ParsedCommand::parse(&args);
+    match (entry.resolved_output(), parsed.output()) {
+        (Some(output), Some(flag)) if output != resolve(&entry.directory, flag) => report(
+            Severity::Warning,
+            Some("output"),
+            format!(
+                "`{}` does not match the `-o` argument `{}`",
+                output.display(),
+                flag.display()
+            ),
+        ),
+        (Some(output), None) => report(
+            Severity::Warning,
+            Some("output"),
+            format!(
+                "`{}` is given, but there is no `-o` argument",
+                output.display()
+            ),
+        ),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The severity, index and field of a diagnostic
+    type Summary = (Severity, usize, Option<&'static str>);
+
+    fn diagnostics(json: &str) -> Vec<Summary> {
+        serde_json::from_str::<CompilationDatabase>(json)
+            .unwrap()
+            .validate()
+            .into_iter()
+            .map(|d| (d.severity, d.index, d.field))
+            .collect()
+    }
+
+    #[test]
+    fn it_accepts_valid_databases() {
+        let json = r#"[
+            {"directory": "/proj/build", "file": "../main.c", "output": "main.o",
+             "arguments": ["cc", "-c", "-o", "main.o", "../main.c"]},
+            {"directory": "/proj", "file": "/proj/util.c", "command": "cc -c util.c"}
+        ]"#;
+
+        assert_eq!(diagnostics(json), vec![]);
+        let flags = crate::from_compile_flags_txt(Path::new("/proj"), "-Iinclude");
+        assert_eq!(flags.validate(), vec![]);
+    }
+
+    #[test]
+    fn it_reports_entry_errors() {
+        let cases: &[(&str, Vec<Summary>)] = &[
+            (
+                r#"[{"directory": "/p", "file": "a.c"}]"#,
+                vec![(Severity::Error, 0, None)],
+            ),
+            (
+                r#"[{"directory": "", "file": "a.c", "arguments": ["cc", "a.c"]}]"#,
+                vec![(Severity::Error, 0, Some("directory"))],
+            ),
+            (
+                r#"[{"directory": "/p", "file": "", "arguments": ["cc"]}]"#,
+                vec![
+                    (Severity::Error, 0, Some("file")),
+                    (Severity::Warning, 0, Some("file")),
+                ],
+            ),
+            (
+                r#"[{"directory": "/p", "file": "a.c", "arguments": []}]"#,
+                vec![(Severity::Error, 0, Some("arguments"))],
+            ),
+            (
+                r#"[{"directory": "/p", "file": "a.c", "command": "  "}]"#,
+                vec![(Severity::Error, 0, Some("command"))],
+            ),
+            (
+                r#"[{"directory": "/p", "file": "a.c", "command": "cc \"a.c"}]"#,
+                vec![(Severity::Error, 0, Some("command"))],
+            ),
+        ];
+
+        for (json, expected) in cases {
+            assert_eq!(diagnostics(json), *expected, "validating {json}");
+        }
+    }
+
+    #[test]
+    fn it_reports_entry_warnings() {
+        let cases: &[(&str, Vec<Summary>)] = &[
+            (
+                r#"[{"directory": "build", "file": "a.c", "arguments": ["cc", "a.c"]}]"#,
+                vec![(Severity::Warning, 0, Some("directory"))],
+            ),
+            (
+                r#"[{"directory": "/p", "file": "a.c", "arguments": ["cc", "b.c"]}]"#,
+                vec![(Severity::Warning, 0, Some("file"))],
+            ),
+            (
+                r#"[{"directory": "/p/build", "file": "/p/a.c", "arguments": ["cc", "../a.c"]}]"#,
+                vec![],
+            ),
+            (
+                r#"[{"directory": "/p", "file": "a.c", "output": "a.o",
+                     "arguments": ["cc", "-o", "b.o", "a.c"]}]"#,
+                vec![(Severity::Warning, 0, Some("output"))],
+            ),
+            (
+                r#"[{"directory": "/p", "file": "a.c", "output": "a.o",
+                     "arguments": ["cc", "-c", "a.c"]}]"#,
+                vec![(Severity::Warning, 0, Some("output"))],
+            ),
+            (
+                r#"[{"directory": "/p", "file": "a.c", "output": "/p/a.o",
+                     "arguments": ["cc", "-oa.o", "a.c"]}]"#,
+                vec![],
+            ),
+            (
+                r#"[{"directory": "/p", "file": "a.c",
+                     "arguments": ["cc", "a.c"], "command": "cc -O2 a.c"}]"#,
+                vec![(Severity::Warning, 0, None)],
+            ),
+        ];
+
+        for (json, expected) in cases {
+            assert_eq!(diagnostics(json), *expected, "validating {json}");
+        }
+    }
+
+    #[test]
+    fn it_reports_duplicate_entries() {
+        let json = r#"[
+            {"directory": "/p", "file": "a.c", "arguments": ["cc", "a.c"]},
+            {"directory": "/p", "file": "a.c", "arguments": ["cc", "-O2", "a.c"]},
+            {"directory": "/p", "file": "a.c", "arguments": ["cc", "a.c"]},
+            {"directory": "/p", "file": "a.c", "arguments": ["cc", "a.c"]}
+        ]"#;
+
+        let db = serde_json::from_str::<CompilationDatabase>(json).unwrap();
+        let diagnostics = db.validate();
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| (d.severity, d.index, d.field))
+                .collect::<Vec<_>>(),
+            vec![(Severity::Warning, 2, None), (Severity::Warning, 3, None)]
+        );
+        assert_eq!(
+            diagnostics[1].to_string(),
+            "warning: entry 3: duplicate of entry 0"
+        );
+    }
+
+    #[test]
+    fn it_displays_diagnostics() {
+        let diagnostic = Diagnostic {
+            severity: Severity::Error,
+            index: 4,
+            field: Some("directory"),
+            message: String::from("`directory` is empty"),
+        };
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: entry 4, field `directory`: `directory` is empty"
+        );
+        assert!(Severity::Error > Severity::Warning);
+    }
+}